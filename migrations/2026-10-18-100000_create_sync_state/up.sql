@@ -0,0 +1,7 @@
+-- Persisted cursors for the block synchronizers. `height` is the highest
+-- block height below which every transaction has been indexed.
+CREATE TABLE sync_state (
+    name TEXT PRIMARY KEY,
+    height BIGINT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,15 @@
+-- Every block height range whose transactions were completely indexed.
+-- Heights not covered by any row are gaps to be backfilled.
+CREATE TABLE indexed_block_range (
+    start_height BIGINT NOT NULL,
+    end_height BIGINT NOT NULL,
+    indexed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (start_height, end_height)
+);
+
+CREATE INDEX indexed_block_range_end_height_index ON indexed_block_range (end_height);
+
+-- Everything up to the historical sync cursor was indexed before ranges
+-- were recorded.
+INSERT INTO indexed_block_range (start_height, end_height)
+SELECT 1, height FROM sync_state WHERE name = 'historical' AND height > 0;
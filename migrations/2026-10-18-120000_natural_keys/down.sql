@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX cw20_transactions_natural_key_index;
+
+ALTER TABLE cw20_transactions
+    DROP COLUMN tx_hash,
+    DROP COLUMN msg_index,
+    DROP COLUMN event_index;
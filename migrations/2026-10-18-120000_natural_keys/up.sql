@@ -0,0 +1,10 @@
+-- Rows written while indexing a transaction carry the transaction hash,
+-- the message index within the transaction and the event index within the
+-- message, so replaying a transaction can't duplicate them.
+ALTER TABLE cw20_transactions
+    ADD COLUMN tx_hash TEXT,
+    ADD COLUMN msg_index INT,
+    ADD COLUMN event_index INT;
+
+CREATE UNIQUE INDEX cw20_transactions_natural_key_index
+    ON cw20_transactions (tx_hash, msg_index, event_index);
@@ -0,0 +1,13 @@
+-- tx_search requests that exhausted their retry budget during a sync.
+CREATE TABLE failed_tx_search (
+    id SERIAL PRIMARY KEY,
+    start_height BIGINT NOT NULL,
+    end_height BIGINT NOT NULL,
+    query TEXT NOT NULL,
+    page INT NOT NULL,
+    attempts INT NOT NULL,
+    error TEXT,
+    failed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX failed_tx_search_start_height_index ON failed_tx_search (start_height);
@@ -0,0 +1,2 @@
+-- RFC 3339 timestamp from the block header.
+ALTER TABLE block ADD COLUMN time TEXT;
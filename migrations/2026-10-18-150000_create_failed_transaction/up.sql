@@ -0,0 +1,13 @@
+-- Transactions the chain rejected (a non-zero result code). They are kept
+-- out of the domain tables unless an indexer opts in to seeing them.
+CREATE TABLE failed_transaction (
+    hash TEXT PRIMARY KEY,
+    height BIGINT NOT NULL,
+    tx_index INT NOT NULL,
+    code INT NOT NULL,
+    codespace TEXT NOT NULL,
+    log TEXT NOT NULL,
+    recorded_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX failed_transaction_height_index ON failed_transaction (height);
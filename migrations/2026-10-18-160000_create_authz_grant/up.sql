@@ -0,0 +1,20 @@
+-- Authz grants, one row per MsgGrant, so who could act for an account (a
+-- DAO, say) and when can be audited. A MsgRevoke marks the grants it ends.
+CREATE TABLE authz_grant (
+    id SERIAL PRIMARY KEY,
+    granter TEXT NOT NULL,
+    grantee TEXT NOT NULL,
+    -- The message the grantee may execute for the granter.
+    msg_type_url TEXT NOT NULL,
+    authorization_type_url TEXT NOT NULL,
+    -- RFC 3339, or NULL if the grant doesn't expire.
+    expiration TEXT,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    msg_index INT NOT NULL,
+    revoked_height BIGINT,
+    revoked_tx_hash TEXT
+);
+
+CREATE UNIQUE INDEX authz_grant_natural_key_index ON authz_grant (tx_hash, msg_index);
+CREATE INDEX authz_grant_granter_index ON authz_grant (granter, grantee, msg_type_url);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE contract_history;
+ALTER TABLE codes DROP COLUMN checksum;
@@ -0,0 +1,25 @@
+-- SHA-256 of the uncompressed wasm, in lowercase hex as wasmd reports it.
+ALTER TABLE codes ADD COLUMN checksum TEXT;
+
+-- Every change to a contract's code or admin, starting with its
+-- instantiation, so which code a contract ran and who could migrate it can
+-- be traced back.
+CREATE TABLE contract_history (
+    id SERIAL PRIMARY KEY,
+    contract_address TEXT NOT NULL,
+    -- instantiate, migrate, update_admin or clear_admin.
+    operation TEXT NOT NULL,
+    -- The code the contract runs afterwards, for instantiate and migrate.
+    code_id BIGINT,
+    -- The admin afterwards, for instantiate and update_admin.
+    admin TEXT,
+    sender TEXT NOT NULL,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    msg_index INT NOT NULL
+);
+
+CREATE UNIQUE INDEX contract_history_natural_key_index
+    ON contract_history (tx_hash, msg_index, contract_address);
+CREATE INDEX contract_history_contract_address_index
+    ON contract_history (contract_address, height);
@@ -0,0 +1,24 @@
+-- Native token movements, one row per coin, from bank sends and from the
+-- coin_spent/coin_received events of messages that move funds indirectly,
+-- such as contracts paying out of a DAO treasury.
+CREATE TABLE bank_transfers (
+    id SERIAL PRIMARY KEY,
+    tx_hash TEXT,
+    msg_index INT NOT NULL,
+    event_index INT NOT NULL,
+    height BIGINT NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    denom TEXT NOT NULL,
+    amount NUMERIC(78) NOT NULL,
+    -- Set when that side of the transfer is a DAO.
+    from_dao_address TEXT REFERENCES dao (contract_address),
+    to_dao_address TEXT REFERENCES dao (contract_address)
+);
+
+CREATE UNIQUE INDEX bank_transfers_natural_key_index
+    ON bank_transfers (tx_hash, msg_index, event_index);
+CREATE INDEX bank_transfers_from_address_index ON bank_transfers (from_address);
+CREATE INDEX bank_transfers_to_address_index ON bank_transfers (to_address);
+CREATE INDEX bank_transfers_from_dao_address_index ON bank_transfers (from_dao_address);
+CREATE INDEX bank_transfers_to_dao_address_index ON bank_transfers (to_dao_address);
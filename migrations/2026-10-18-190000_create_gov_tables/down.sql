@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE gov_vote;
+DROP TABLE gov_deposit;
+DROP TABLE gov_proposal;
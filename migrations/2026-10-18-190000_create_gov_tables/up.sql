@@ -0,0 +1,51 @@
+-- Chain governance (x/gov), kept apart from DAO proposals so the two can be
+-- compared.
+CREATE TABLE gov_proposal (
+    proposal_id BIGINT PRIMARY KEY,
+    proposer TEXT NOT NULL,
+    -- The type of the proposal's content, like
+    -- /cosmos.gov.v1beta1.TextProposal.
+    content_type_url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    -- When deposits reached the minimum and voting opened.
+    voting_start_height BIGINT
+);
+
+-- One row per coin deposited, the initial deposit included.
+CREATE TABLE gov_deposit (
+    id SERIAL PRIMARY KEY,
+    proposal_id BIGINT NOT NULL,
+    depositor TEXT NOT NULL,
+    denom TEXT NOT NULL,
+    amount NUMERIC(78) NOT NULL,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    msg_index INT NOT NULL,
+    event_index INT NOT NULL
+);
+
+CREATE UNIQUE INDEX gov_deposit_natural_key_index
+    ON gov_deposit (tx_hash, msg_index, event_index);
+CREATE INDEX gov_deposit_proposal_id_index ON gov_deposit (proposal_id);
+
+-- One row per option of each vote; a plain vote is one option weighted 1.
+-- Voters can vote again, and their latest vote is the one that counts.
+CREATE TABLE gov_vote (
+    id SERIAL PRIMARY KEY,
+    proposal_id BIGINT NOT NULL,
+    voter TEXT NOT NULL,
+    -- yes, abstain, no or no_with_veto.
+    option TEXT NOT NULL,
+    weight NUMERIC NOT NULL,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    msg_index INT NOT NULL,
+    event_index INT NOT NULL
+);
+
+CREATE UNIQUE INDEX gov_vote_natural_key_index
+    ON gov_vote (tx_hash, msg_index, event_index);
+CREATE INDEX gov_vote_proposal_id_index ON gov_vote (proposal_id, voter);
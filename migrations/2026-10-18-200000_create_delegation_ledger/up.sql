@@ -0,0 +1,42 @@
+-- Every change to a delegation, one row per coin: delegations, undelegations,
+-- both halves of a redelegation, and the rewards withdrawn along the way.
+CREATE TABLE delegation_ledger (
+    id SERIAL PRIMARY KEY,
+    delegator TEXT NOT NULL,
+    validator TEXT NOT NULL,
+    -- delegate, undelegate, redelegate_out, redelegate_in or
+    -- withdraw_reward.
+    operation TEXT NOT NULL,
+    denom TEXT NOT NULL,
+    amount NUMERIC(78) NOT NULL,
+    -- RFC 3339, for undelegations and redelegations: when the tokens are
+    -- released.
+    completion_time TEXT,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    msg_index INT NOT NULL,
+    event_index INT NOT NULL
+);
+
+CREATE UNIQUE INDEX delegation_ledger_natural_key_index
+    ON delegation_ledger (tx_hash, msg_index, event_index);
+CREATE INDEX delegation_ledger_delegator_index ON delegation_ledger (delegator, validator);
+
+-- What each delegator has delegated to each validator, going by the ledger.
+-- Slashing isn't recorded, so this can overstate slashed delegations.
+CREATE VIEW delegation_balance AS
+SELECT
+    delegator,
+    validator,
+    denom,
+    SUM(
+        CASE operation
+            WHEN 'delegate' THEN amount
+            WHEN 'redelegate_in' THEN amount
+            WHEN 'undelegate' THEN -amount
+            WHEN 'redelegate_out' THEN -amount
+            ELSE 0
+        END
+    ) AS balance
+FROM delegation_ledger
+GROUP BY delegator, validator, denom;
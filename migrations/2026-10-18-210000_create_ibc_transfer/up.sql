@@ -0,0 +1,38 @@
+-- ICS-20 token transfers in and out of the chain. A packet is identified by
+-- the port, channel and sequence it was sent from, which is how outgoing
+-- transfers are matched with their acknowledgement or timeout.
+CREATE TABLE ibc_transfer (
+    id SERIAL PRIMARY KEY,
+    -- outgoing or incoming.
+    direction TEXT NOT NULL,
+    source_port TEXT NOT NULL,
+    source_channel TEXT NOT NULL,
+    sequence BIGINT NOT NULL,
+    destination_port TEXT NOT NULL,
+    destination_channel TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    receiver TEXT NOT NULL,
+    -- The denom on this chain: a base denom, or ibc/ and the hash of the
+    -- trace.
+    denom TEXT NOT NULL,
+    -- The path the tokens took to this chain and their denom where they
+    -- came from, like transfer/channel-0/uatom.
+    denom_trace TEXT NOT NULL,
+    base_denom TEXT NOT NULL,
+    amount NUMERIC(78) NOT NULL,
+    -- Outgoing transfers are pending until acknowledged, failed (an error
+    -- acknowledgement, which refunds the sender) or timed_out. Incoming
+    -- ones are received or receive_failed.
+    status TEXT NOT NULL,
+    error TEXT,
+    height BIGINT NOT NULL,
+    tx_hash TEXT,
+    -- Where an outgoing transfer was acknowledged or timed out.
+    resolved_height BIGINT,
+    resolved_tx_hash TEXT
+);
+
+CREATE UNIQUE INDEX ibc_transfer_packet_index
+    ON ibc_transfer (direction, source_port, source_channel, sequence);
+CREATE INDEX ibc_transfer_sender_index ON ibc_transfer (sender);
+CREATE INDEX ibc_transfer_receiver_index ON ibc_transfer (receiver);
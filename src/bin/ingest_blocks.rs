@@ -8,6 +8,7 @@ use dao_indexer::db::persister::{make_persister_ref, StubPersister};
 use dao_indexer::historical_parser::block_synchronizer;
 use dao_indexer::indexing::indexer_registry::IndexerRegistry;
 use dao_indexer::indexing::msg_set::default_msg_set;
+use dao_indexer::shutdown::ShutdownSignal;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -29,6 +30,7 @@ async fn main() -> anyhow::Result<()> {
     let indexer_registry = IndexerRegistry::new(Some(db), None, persister_ref);
     let msg_set = default_msg_set();
 
-    block_synchronizer(&indexer_registry, &config, msg_set.clone()).await?;
+    let shutdown = ShutdownSignal::from_os_signals();
+    block_synchronizer(&indexer_registry, &config, msg_set.clone(), &shutdown).await?;
     Ok(())
 }
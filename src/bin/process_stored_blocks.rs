@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use clap::Command;
 use dao_indexer::db::db_persister::DatabasePersister;
 use dao_indexer::db::persister::{make_persister_ref, PersisterRef, StubPersister};
@@ -8,7 +9,6 @@ use sea_orm::{Database, DatabaseConnection};
 
 use dao_indexer::config::IndexerConfig;
 use dao_indexer::db::connection::establish_connection;
-use dao_indexer::historical_parser::index_search_result;
 use dao_indexer::indexing::indexer_registry::{IndexerRegistry, Register};
 use dao_indexer::indexing::indexers::msg_cw20_indexer::Cw20ExecuteMsgIndexer;
 use dao_indexer::indexing::indexers::msg_cw3dao_indexer::{
@@ -19,7 +19,10 @@ use dao_indexer::indexing::indexers::msg_cw3multisig_indexer::{
 };
 use dao_indexer::indexing::indexers::msg_stake_cw20_indexer::StakeCw20ExecuteMsgIndexer;
 use dao_indexer::indexing::msg_set::default_msg_set;
-use dao_indexer::util::transaction_util::get_transactions;
+use dao_indexer::shutdown::ShutdownSignal;
+use dao_indexer::source::db_source::DbSource;
+use dao_indexer::source::index_source;
+use dao_indexer::util::height_ranges::HeightRange;
 
 fn init_registry(registry: &mut IndexerRegistry) -> anyhow::Result<()> {
     let cw20_indexer = Cw20ExecuteMsgIndexer::default();
@@ -38,19 +41,30 @@ fn init_registry(registry: &mut IndexerRegistry) -> anyhow::Result<()> {
     registry.initialize()
 }
 
-fn process_transactions(config: &IndexerConfig, registry: &IndexerRegistry) -> anyhow::Result<()> {
-    let txs = get_transactions(config, registry)?;
+async fn process_transactions(
+    config: &IndexerConfig,
+    registry: &IndexerRegistry,
+) -> anyhow::Result<()> {
+    let db = registry.db.as_ref().ok_or_else(|| {
+        anyhow!("Error: You need to define the database if you're trying to read from it.")
+    })?;
+    // Stored transactions strictly between the initial and final heights.
+    let range = HeightRange::new(
+        config.tendermint_initial_block + 1,
+        config.tendermint_final_block.saturating_sub(1),
+    );
 
-    info!("Linearly processing {} transactions \n", txs.len());
+    info!("Linearly processing transactions in blocks {}\n", range);
 
-    for tx in txs {
-        index_search_result(&tx, registry, config, default_msg_set())?;
-    }
+    let mut source = DbSource::new(db, range, config.block_page_size);
+    let shutdown = ShutdownSignal::from_os_signals();
+    let indexed = index_source(&mut source, registry, config, default_msg_set(), &shutdown).await?;
 
+    info!("Processed {} transactions", indexed);
     Ok(())
 }
 
-fn persist_historical_transactions(
+async fn persist_historical_transactions(
     config: &IndexerConfig,
     diesel_db: PgConnection,
     persister_connection: DatabaseConnection,
@@ -59,7 +73,7 @@ fn persist_historical_transactions(
     let mut registry =
         IndexerRegistry::new(Some(diesel_db), Some(persister_connection), persister_ref);
     init_registry(&mut registry)?;
-    process_transactions(config, &registry)
+    process_transactions(config, &registry).await
 }
 
 #[tokio::main]
@@ -91,13 +105,14 @@ async fn main() -> anyhow::Result<()> {
             diesel_db,
             persister_connection,
             persister_ref.clone(),
-        )?;
+        )
+        .await?;
         drop(persister_ref)
     } else {
         let mut registry =
             IndexerRegistry::new(None, None, make_persister_ref(Box::from(StubPersister {})));
         init_registry(&mut registry)?;
-        return process_transactions(&config, &registry);
+        return process_transactions(&config, &registry).await;
     };
     Ok(())
 }
@@ -0,0 +1,125 @@
+use crate::config::IndexerConfig;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::rpc::client_pool::ClientPool;
+use crate::shutdown::ShutdownSignal;
+use crate::util::block_util::insert_block;
+use crate::util::height_ranges::HeightRange;
+use crate::util::history_util::tx_bytes_hash;
+use crate::util::query_stream::backoff_delay;
+use futures::stream::{self, StreamExt};
+use log::{debug, info};
+use tendermint::block::Block;
+use tendermint_rpc::endpoint::block::Response as BlockResponse;
+use tendermint_rpc::endpoint::block_results::Response as BlockResultsResponse;
+use tendermint_rpc::endpoint::tx::Response;
+
+/// Pairs each transaction in `block` with its `DeliverTx` from `results`,
+/// producing the same responses `tx_search` would have.
+pub fn block_tx_responses(
+    block: &Block,
+    results: &BlockResultsResponse,
+) -> anyhow::Result<Vec<Response>> {
+    let txs: Vec<_> = block.data.iter().collect();
+    let tx_results = results.txs_results.clone().unwrap_or_default();
+    if txs.len() != tx_results.len() {
+        return Err(anyhow::anyhow!(
+            "block {} has {} transactions but {} results",
+            block.header.height,
+            txs.len(),
+            tx_results.len()
+        ));
+    }
+    Ok(txs
+        .into_iter()
+        .zip(tx_results)
+        .enumerate()
+        .map(|(index, (tx, tx_result))| Response {
+            hash: tx_bytes_hash(tx),
+            height: block.header.height,
+            index: index as u32,
+            tx_result,
+            tx: tx.clone(),
+            proof: None,
+        })
+        .collect())
+}
+
+async fn fetch_block(
+    clients: &ClientPool,
+    height: u64,
+) -> anyhow::Result<(BlockResponse, Vec<Response>)> {
+    let (block_response, block_results) =
+        futures::try_join!(clients.block(height), clients.block_results(height))?;
+    let tx_responses = block_tx_responses(&block_response.block, &block_results)?;
+    Ok((block_response, tx_responses))
+}
+
+// Fetches a block and its results, retrying with backoff up to the
+// configured retry budget.
+async fn fetch_block_with_retries(
+    clients: &ClientPool,
+    config: &IndexerConfig,
+    height: u64,
+) -> anyhow::Result<(BlockResponse, Vec<Response>)> {
+    let mut failures = 0;
+    loop {
+        match fetch_block(clients, height).await {
+            Ok(block) => return Ok(block),
+            Err(e) => {
+                failures += 1;
+                if failures > config.max_request_retries {
+                    return Err(e.context(format!(
+                        "block {} failed after {} attempts",
+                        height, failures
+                    )));
+                }
+                let delay = backoff_delay(
+                    config.retry_base_sleep,
+                    config.max_retry_sleep,
+                    failures,
+                    rand::random(),
+                );
+                debug!(
+                    "Error fetching block {}: {:?}, retrying in {:?}",
+                    height, e, delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Fetches every block in `range` with the `block` and `block_results`
+/// endpoints, which don't depend on the node's tx indexer, saving the
+/// blocks and returning their transactions in height order. On shutdown no
+/// further blocks are requested and the range is abandoned.
+pub async fn fetch_block_range_blocks(
+    clients: &ClientPool,
+    config: &IndexerConfig,
+    registry: &IndexerRegistry,
+    range: HeightRange,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<Response>> {
+    info!("loading blocks {}", range);
+    let mut blocks = stream::iter(range.start..=range.end)
+        .map(|height| fetch_block_with_retries(clients, config, height))
+        .buffered(config.max_requests.max(1) as usize);
+    let mut txs = vec![];
+    while let Some(fetched) = blocks.next().await {
+        if shutdown.is_shutdown() {
+            return Err(anyhow::anyhow!("abandoned blocks {} to shut down", range));
+        }
+        let (block_response, tx_responses) = fetched?;
+        if let Some(db) = &registry.db {
+            if !tx_responses.is_empty() || config.tendermint_save_all_blocks {
+                insert_block(
+                    db,
+                    &block_response.block_id.hash.to_string(),
+                    &block_response.block,
+                )?;
+            }
+        }
+        txs.extend(tx_responses);
+    }
+    Ok(txs)
+}
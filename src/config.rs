@@ -2,12 +2,50 @@ use clap::{Arg, Command};
 use dotenvy::dotenv;
 use std::env;
 use std::fmt;
+use std::str::FromStr;
+
+/// How historical transactions are fetched from RPC nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IngestionMode {
+    /// `tx_search` over `tx.height` ranges. Requires the node's tx indexer.
+    TxSearch,
+    /// `block` and `block_results` for every height. Works against nodes
+    /// with the tx indexer disabled, and fills the `block` table.
+    Blocks,
+}
+
+impl FromStr for IngestionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "tx_search" => Ok(IngestionMode::TxSearch),
+            "blocks" => Ok(IngestionMode::Blocks),
+            _ => Err(anyhow::anyhow!("Unknown ingestion mode {}", mode)),
+        }
+    }
+}
+
+impl fmt::Display for IngestionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestionMode::TxSearch => write!(f, "tx_search"),
+            IngestionMode::Blocks => write!(f, "blocks"),
+        }
+    }
+}
 
 pub struct IndexerConfig {
     pub enable_indexer_env: bool,
     pub tendermint_websocket_url: String,
     pub tendermint_rpc_url: String,
+    pub tendermint_rpc_urls: Vec<String>,
+    pub rpc_max_errors: u32,
+    pub rpc_eviction_seconds: u64,
+    pub rpc_rate_limits: Vec<f64>,
+    pub rpc_burst: u32,
     pub tendermint_initial_block: u64,
+    pub tendermint_force_initial_block: bool,
     pub tendermint_final_block: u64,
     pub tendermint_save_all_blocks: bool,
     pub postgres_backend: bool,
@@ -15,11 +53,26 @@ pub struct IndexerConfig {
     pub transaction_page_size: u8,
     pub block_page_size: u64,
     pub max_requests: u8,
+    pub reorder_window: usize,
     pub max_empty_block_retries: u32,
     pub listen: bool,
     pub requeue_sleep: u64,
+    pub retry_base_sleep: u64,
+    pub max_retry_sleep: u64,
+    pub max_request_retries: u32,
+    pub reconnect_sleep: u64,
+    pub max_reconnect_sleep: u64,
     pub write_transactions_in_database: bool,
     pub schema_indexer: bool,
+    pub backfill: bool,
+    pub ingestion_mode: IngestionMode,
+    pub archive_record_dir: String,
+    pub archive_replay_dir: String,
+    pub archive_blocks_per_file: u64,
+    pub failed_tx_indexers: Vec<String>,
+    pub http_listen_address: String,
+    pub liveness_timeout_seconds: u64,
+    pub readiness_max_lag: u64,
 }
 
 impl IndexerConfig {
@@ -70,80 +123,240 @@ impl IndexerConfig {
     }
 
     fn init() -> Self {
-        let enable_indexer_env = env::var("ENABLE_INDEXER")
+        Self::from_vars(|key| env::var(key))
+    }
+
+    /// The configuration with every setting at its default, ignoring the
+    /// environment and any .env file.
+    pub fn with_defaults() -> Self {
+        Self::from_vars(|_| Err(env::VarError::NotPresent))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Result<String, env::VarError>) -> Self {
+        let enable_indexer_env = var("ENABLE_INDEXER")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
             .unwrap_or(false);
-        let tendermint_websocket_url = env::var("TENDERMINT_WEBSOCKET_URL")
+        let tendermint_websocket_url = var("TENDERMINT_WEBSOCKET_URL")
             .unwrap_or_else(|_| "ws://127.0.0.1:26657/websocket".to_string());
-        let database_url = env::var("DATABASE_URL")
+        let database_url = var("DATABASE_URL")
             .unwrap_or_else(|_| "postgres://user@localhost:5432/daodaoindexer".to_string());
         let tendermint_rpc_url =
-            env::var("TENDERMINT_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:26657".to_string());
-        let tendermint_initial_block = env::var("TENDERMINT_INITIAL_BLOCK_HEIGHT")
+            var("TENDERMINT_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:26657".to_string());
+        // Comma-separated RPC endpoints to spread historical requests across.
+        let tendermint_rpc_urls: Vec<String> = var("TENDERMINT_RPC_URLS")
+            .unwrap_or_else(|_| tendermint_rpc_url.clone())
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        // An RPC endpoint that fails this many requests in a row is left out
+        // of the pool for RPC_EVICTION_SECONDS.
+        let rpc_max_errors = var("RPC_MAX_ERRORS")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .unwrap_or(3);
+        let rpc_eviction_seconds = var("RPC_EVICTION_SECONDS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .unwrap_or(60);
+        // Requests per second sent to each RPC endpoint (0 for no limit).
+        // TENDERMINT_RPC_RATE_LIMITS optionally gives a comma-separated rate
+        // for each of TENDERMINT_RPC_URLS, in the same order.
+        let rpc_requests_per_second = var("RPC_REQUESTS_PER_SECOND")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<f64>()
+            .unwrap_or(10.0);
+        let endpoint_rate_limits: Vec<Option<f64>> = var("TENDERMINT_RPC_RATE_LIMITS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|rate| rate.trim().parse::<f64>().ok())
+            .collect();
+        let rpc_rate_limits: Vec<f64> = (0..tendermint_rpc_urls.len())
+            .map(|i| {
+                endpoint_rate_limits
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or(rpc_requests_per_second)
+            })
+            .collect();
+        let rpc_burst = var("RPC_BURST")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .unwrap_or(10);
+        let tendermint_initial_block = var("TENDERMINT_INITIAL_BLOCK_HEIGHT")
             .unwrap_or_else(|_| "1".to_string())
             .parse::<u64>()
             .unwrap_or(1);
-        let tendermint_final_block = env::var("TENDERMINT_FINAL_BLOCK_HEIGHT")
+        // Historical indexing resumes from the persisted sync cursor unless
+        // this is set, in which case it starts at TENDERMINT_INITIAL_BLOCK_HEIGHT.
+        let tendermint_force_initial_block = var("TENDERMINT_FORCE_INITIAL_BLOCK_HEIGHT")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+        let tendermint_final_block = var("TENDERMINT_FINAL_BLOCK_HEIGHT")
             .unwrap_or_else(|_| "0".to_string())
             .parse::<u64>()
             .unwrap_or(0);
-        let tendermint_save_all_blocks = env::var("TENDERMINT_SAVE_ALL_BLOCKS")
+        let tendermint_save_all_blocks = var("TENDERMINT_SAVE_ALL_BLOCKS")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
             .unwrap_or(false);
 
         // By default we use a postgres database for the backend, but not always!
-        let postgres_backend = env::var("POSTGRES_PERSISTENCE")
+        let postgres_backend = var("POSTGRES_PERSISTENCE")
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
             .unwrap_or(true);
 
-        let listen = env::var("TENDERMINT_WEBSOCKET_LISTEN")
+        let listen = var("TENDERMINT_WEBSOCKET_LISTEN")
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
             .unwrap_or(true);
 
-        let transaction_page_size: u8 = env::var("TRANSACTION_PAGE_SIZE")
+        let transaction_page_size: u8 = var("TRANSACTION_PAGE_SIZE")
             .unwrap_or_else(|_| "100".to_string())
             .parse::<u8>()
             .unwrap_or(100);
 
-        let block_page_size: u64 = env::var("BLOCK_PAGE_SIZE")
+        let block_page_size: u64 = var("BLOCK_PAGE_SIZE")
             .unwrap_or_else(|_| "100".to_string())
             .parse::<u64>()
             .unwrap_or(100);
 
-        let max_requests: u8 = env::var("MAX_REQUESTS")
+        let max_requests: u8 = var("MAX_REQUESTS")
             .unwrap_or_else(|_| "10".to_string())
             .parse::<u8>()
             .unwrap_or(10);
 
-        let max_empty_block_retries: u32 = env::var("MAX_EMPTY_BLOCK_RETRIES")
+        // How many block ranges may be fetched ahead of the one being
+        // indexed. Ranges are always indexed in height order.
+        let reorder_window: usize = var("REORDER_WINDOW")
+            .unwrap_or_else(|_| max_requests.to_string())
+            .parse::<usize>()
+            .unwrap_or(max_requests as usize);
+
+        let max_empty_block_retries: u32 = var("MAX_EMPTY_BLOCK_RETRIES")
             .unwrap_or_else(|_| "500".to_string())
             .parse::<u32>()
             .unwrap_or(500);
 
-        let requeue_sleep: u64 = env::var("REQUEUE_SLEEP")
+        // Delay in milliseconds before looking again at blocks whose first
+        // page of transactions came back empty.
+        let requeue_sleep: u64 = var("REQUEUE_SLEEP")
             .unwrap_or_else(|_| "0".to_string())
             .parse::<u64>()
             .unwrap_or(0);
 
-        let write_transactions_in_database: bool = env::var("STORE_TRANSACTIONS")
+        // Base delay in milliseconds before retrying a failed RPC request.
+        // Each further failure doubles it, up to MAX_RETRY_SLEEP.
+        let retry_base_sleep: u64 = var("RETRY_BASE_SLEEP")
+            .unwrap_or_else(|_| "250".to_string())
+            .parse::<u64>()
+            .unwrap_or(250);
+
+        let max_retry_sleep: u64 = var("MAX_RETRY_SLEEP")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()
+            .unwrap_or(30000);
+
+        // How many times a failed RPC request is retried before it's given up
+        // on and recorded in the failed_tx_search table.
+        let max_request_retries: u32 = var("MAX_REQUEST_RETRIES")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .unwrap_or(10);
+
+        // Base delay in milliseconds before reconnecting a dropped websocket
+        // subscription, doubling on each failed attempt up to
+        // MAX_RECONNECT_SLEEP.
+        let reconnect_sleep: u64 = var("RECONNECT_SLEEP")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()
+            .unwrap_or(1000);
+
+        let max_reconnect_sleep: u64 = var("MAX_RECONNECT_SLEEP")
+            .unwrap_or_else(|_| "60000".to_string())
+            .parse::<u64>()
+            .unwrap_or(60000);
+
+        let write_transactions_in_database: bool = var("STORE_TRANSACTIONS")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
             .unwrap_or(true);
 
-        let schema_indexer = env::var("USE_SCHEMA_INDEXER")
+        let schema_indexer = var("USE_SCHEMA_INDEXER")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .unwrap_or(false);
+
+        // Instead of a linear historical sync, only re-fetch the ranges between
+        // TENDERMINT_INITIAL_BLOCK_HEIGHT and TENDERMINT_FINAL_BLOCK_HEIGHT
+        // that have not been indexed.
+        let backfill = var("BACKFILL_MISSING_BLOCKS")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
             .unwrap_or(false);
 
+        // "tx_search" (default) or "blocks"
+        let ingestion_mode = var("INGESTION_MODE")
+            .unwrap_or_else(|_| "tx_search".to_string())
+            .parse::<IngestionMode>()
+            .unwrap_or(IngestionMode::TxSearch);
+
+        // Directory to record every RPC response in, as gzipped
+        // newline-delimited JSON files of ARCHIVE_BLOCKS_PER_FILE blocks each.
+        let archive_record_dir = var("ARCHIVE_RECORD_DIR").unwrap_or_else(|_| "".to_string());
+
+        // Directory of recorded responses to index instead of talking to a
+        // node.
+        let archive_replay_dir = var("ARCHIVE_REPLAY_DIR").unwrap_or_else(|_| "".to_string());
+
+        let archive_blocks_per_file: u64 = var("ARCHIVE_BLOCKS_PER_FILE")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse::<u64>()
+            .unwrap_or(10000);
+
+        // Comma-separated ids of the indexers that are handed messages from
+        // failed transactions. Failed transactions are always recorded in the
+        // failed_transaction table, but by default nothing indexes them.
+        let failed_tx_indexers: Vec<String> = var("FAILED_TX_INDEXERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+
+        // Address (e.g. 0.0.0.0:9090) to serve Prometheus metrics on at
+        // /metrics, and liveness and readiness checks on /healthz and /readyz.
+        // Not served if empty.
+        let http_listen_address = var("HTTP_LISTEN_ADDRESS").unwrap_or_else(|_| "".to_string());
+
+        // /healthz fails once the indexer has made no progress for this long.
+        let liveness_timeout_seconds: u64 = var("LIVENESS_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .unwrap_or(300);
+
+        // /readyz fails while the indexer is more than this many blocks
+        // behind the chain head.
+        let readiness_max_lag: u64 = var("READINESS_MAX_LAG")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse::<u64>()
+            .unwrap_or(20);
+
         IndexerConfig {
             enable_indexer_env,
             tendermint_websocket_url,
             tendermint_rpc_url,
+            tendermint_rpc_urls,
+            rpc_max_errors,
+            rpc_eviction_seconds,
+            rpc_rate_limits,
+            rpc_burst,
             tendermint_initial_block,
+            tendermint_force_initial_block,
             tendermint_final_block,
             tendermint_save_all_blocks,
             postgres_backend,
@@ -152,10 +365,25 @@ impl IndexerConfig {
             transaction_page_size,
             block_page_size,
             max_requests,
+            reorder_window,
             max_empty_block_retries,
             requeue_sleep,
+            retry_base_sleep,
+            max_retry_sleep,
+            max_request_retries,
+            reconnect_sleep,
+            max_reconnect_sleep,
             write_transactions_in_database,
             schema_indexer,
+            backfill,
+            ingestion_mode,
+            archive_record_dir,
+            archive_replay_dir,
+            archive_blocks_per_file,
+            failed_tx_indexers,
+            http_listen_address,
+            liveness_timeout_seconds,
+            readiness_max_lag,
         }
     }
 }
@@ -166,8 +394,14 @@ impl fmt::Display for IndexerConfig {
             f,
             "IndexerConfig:\n\
         tendermint_rpc_url: {}\n\
+        tendermint_rpc_urls: {}\n\
+        rpc_max_errors: {}\n\
+        rpc_eviction_seconds: {}\n\
+        rpc_rate_limits: {:?}\n\
+        rpc_burst: {}\n\
         tendermint_websocket_url: {}\n\
         tendermint_initial_block: {}\n\
+        tendermint_force_initial_block: {}\n\
         tendermint_final_block: {}\n\
         tendermint_save_all_blocks: {}\n\
         postgres_backend: {}\n\
@@ -177,14 +411,35 @@ impl fmt::Display for IndexerConfig {
         transaction_page_size: {}\n\
         block_page_size: {}\n\
         max_requests: {}\n\
+        reorder_window: {}\n\
         max_empty_block_retries: {}\n\
         requeue_sleep: {}\n\
+        retry_base_sleep: {}\n\
+        max_retry_sleep: {}\n\
+        max_request_retries: {}\n\
+        reconnect_sleep: {}\n\
+        max_reconnect_sleep: {}\n\
         write_transactions_in_db: {}\n\
         schema_indexer: {}\n\
+        backfill: {}\n\
+        ingestion_mode: {}\n\
+        archive_record_dir: {}\n\
+        archive_replay_dir: {}\n\
+        archive_blocks_per_file: {}\n\
+        failed_tx_indexers: {}\n\
+        http_listen_address: {}\n\
+        liveness_timeout_seconds: {}\n\
+        readiness_max_lag: {}\n\
        ",
             self.tendermint_rpc_url,
+            self.tendermint_rpc_urls.join(","),
+            self.rpc_max_errors,
+            self.rpc_eviction_seconds,
+            self.rpc_rate_limits,
+            self.rpc_burst,
             self.tendermint_websocket_url,
             self.tendermint_initial_block,
+            self.tendermint_force_initial_block,
             self.tendermint_final_block,
             self.tendermint_save_all_blocks,
             self.postgres_backend,
@@ -194,10 +449,25 @@ impl fmt::Display for IndexerConfig {
             self.transaction_page_size,
             self.block_page_size,
             self.max_requests,
+            self.reorder_window,
             self.max_empty_block_retries,
             self.requeue_sleep,
+            self.retry_base_sleep,
+            self.max_retry_sleep,
+            self.max_request_retries,
+            self.reconnect_sleep,
+            self.max_reconnect_sleep,
             self.write_transactions_in_database,
-            self.schema_indexer
+            self.schema_indexer,
+            self.backfill,
+            self.ingestion_mode,
+            self.archive_record_dir,
+            self.archive_replay_dir,
+            self.archive_blocks_per_file,
+            self.failed_tx_indexers.join(","),
+            self.http_listen_address,
+            self.liveness_timeout_seconds,
+            self.readiness_max_lag
         )
     }
 }
@@ -2,13 +2,14 @@ use log::debug;
 use sea_orm::sea_query::{
     Alias, ColumnDef, ForeignKeyCreateStatement, PostgresQueryBuilder, Table, TableCreateStatement,
 };
-use sea_orm::{ConnectionTrait, DatabaseConnection};
+use sea_orm::{ConnectionTrait, DatabaseConnection, Statement};
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use super::db_mapper::{DatabaseMapper, FieldMappingPolicy};
 use super::db_util::{
     db_column_name, db_table_name, foreign_key, DEFAULT_ID_COLUMN_NAME,
-    DEFAULT_TABLE_NAME_COLUMN_NAME, TARGET_ID_COLUMN_NAME,
+    DEFAULT_TABLE_NAME_COLUMN_NAME, EVENT_INDEX_COLUMN_NAME, MSG_INDEX_COLUMN_NAME,
+    TARGET_ID_COLUMN_NAME, TX_HASH_COLUMN_NAME,
 };
 
 #[derive(Debug)]
@@ -198,6 +199,12 @@ impl DatabaseBuilder {
             for (_col_name, col_def) in column_defs.iter_mut() {
                 statement = statement.col(col_def);
             }
+            // Natural key of the message a record was indexed from, so
+            // re-indexing the same transaction doesn't duplicate records.
+            statement
+                .col(ColumnDef::new(Alias::new(TX_HASH_COLUMN_NAME)).text())
+                .col(ColumnDef::new(Alias::new(MSG_INDEX_COLUMN_NAME)).integer())
+                .col(ColumnDef::new(Alias::new(EVENT_INDEX_COLUMN_NAME)).integer());
         }
         self.columns.clear();
         self
@@ -217,6 +224,11 @@ impl DatabaseBuilder {
 
             seaql_db.execute(statement).await?;
         }
+        for table_name in self.tables.keys() {
+            let statement =
+                Statement::from_string(builder, natural_key_index_sql(&db_table_name(table_name)));
+            seaql_db.execute(statement).await?;
+        }
         // Now that all the tables are created, we can add the rest of the fields and constraints
         for (table_name, constraints) in self.table_constraints.iter() {
             for create_statement in constraints.iter() {
@@ -248,6 +260,18 @@ impl DatabaseBuilder {
     }
 }
 
+/// Unique index over the natural key columns every table gets in
+/// `finalize_columns`. Rows without a transaction hash aren't constrained.
+fn natural_key_index_sql(sql_table_name: &str) -> String {
+    format!(
+        r#"CREATE UNIQUE INDEX IF NOT EXISTS "{table}_natural_key_index" ON "{table}" ("{}", "{}", "{}")"#,
+        TX_HASH_COLUMN_NAME,
+        MSG_INDEX_COLUMN_NAME,
+        EVENT_INDEX_COLUMN_NAME,
+        table = sql_table_name
+    )
+}
+
 impl Default for DatabaseBuilder {
     fn default() -> Self {
         DatabaseBuilder::new()
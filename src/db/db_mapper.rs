@@ -1,7 +1,9 @@
 use super::db_util::{
-    foreign_key, DEFAULT_ID_COLUMN_NAME, DEFAULT_TABLE_NAME_COLUMN_NAME, TARGET_ID_COLUMN_NAME,
+    foreign_key, DEFAULT_ID_COLUMN_NAME, DEFAULT_TABLE_NAME_COLUMN_NAME, EVENT_INDEX_COLUMN_NAME,
+    MSG_INDEX_COLUMN_NAME, TARGET_ID_COLUMN_NAME, TX_HASH_COLUMN_NAME,
 };
 use super::persister::Persister;
+use crate::util::message_key::MessageKey;
 use async_recursion::async_recursion;
 use log::debug;
 use serde::{Deserialize, Serialize};
@@ -234,13 +236,50 @@ impl DatabaseMapper {
         Ok(())
     }
 
-    #[async_recursion]
     pub async fn persist_message(
         &self,
         persister: &dyn Persister<Id = u64>,
         table_name: &str,
         msg: &Value,
         record_id: Option<u64>,
+    ) -> anyhow::Result<u64> {
+        self.persist_message_with_key(persister, table_name, msg, record_id, None)
+            .await
+    }
+
+    /// Persists `msg` like `persist_message`, tagging every inserted record
+    /// with `message_key` so that persisting the same message again is a
+    /// no-op. Records are numbered in the order they're written and that
+    /// number is used as their event index. Skipped records report an id of 0.
+    pub async fn persist_message_with_key(
+        &self,
+        persister: &dyn Persister<Id = u64>,
+        table_name: &str,
+        msg: &Value,
+        record_id: Option<u64>,
+        message_key: Option<&MessageKey>,
+    ) -> anyhow::Result<u64> {
+        let mut next_event_index = 0;
+        self.persist_keyed_message(
+            persister,
+            table_name,
+            msg,
+            record_id,
+            message_key,
+            &mut next_event_index,
+        )
+        .await
+    }
+
+    #[async_recursion]
+    async fn persist_keyed_message(
+        &self,
+        persister: &dyn Persister<Id = u64>,
+        table_name: &str,
+        msg: &Value,
+        record_id: Option<u64>,
+        message_key: Option<&MessageKey>,
+        next_event_index: &mut usize,
     ) -> anyhow::Result<u64> {
         let mapping = self.mappings.get(table_name);
         if mapping.is_none() {
@@ -281,7 +320,14 @@ impl DatabaseMapper {
                     );
                     if let Some(field_mapping) = mapping.get(key) {
                         let child_id = self
-                            .persist_message(persister, &field_mapping.related_table, value, None)
+                            .persist_keyed_message(
+                                persister,
+                                &field_mapping.related_table,
+                                value,
+                                None,
+                                message_key,
+                                next_event_index,
+                            )
                             .await?;
                         let child_id_value = serde_json::json!(child_id);
                         child_id_columns.push(foreign_key(&field_relationship.source_column));
@@ -289,7 +335,16 @@ impl DatabaseMapper {
                     }
                 } else if let Some(submessage_mapping) = self.mappings.get(key) {
                     debug!("submessage_mapping: {:#?}", submessage_mapping);
-                    let child_id = self.persist_message(persister, key, value, None).await?;
+                    let child_id = self
+                        .persist_keyed_message(
+                            persister,
+                            key,
+                            value,
+                            None,
+                            message_key,
+                            next_event_index,
+                        )
+                        .await?;
                     let child_id_value = serde_json::json!(child_id);
                     child_id_columns.push(TARGET_ID_COLUMN_NAME.to_string());
                     child_id_values.push(child_id_value);
@@ -313,7 +368,29 @@ impl DatabaseMapper {
         for child_id_value in child_id_values.iter() {
             values.push(child_id_value);
         }
-        if !columns.is_empty() {
+        if columns.is_empty() {
+            return Ok(db_id);
+        }
+        let message_key = message_key.filter(|key| key.tx_hash.is_some() && record_id.is_none());
+        if let Some(message_key) = message_key {
+            let key = message_key.with_event_index(*next_event_index);
+            *next_event_index += 1;
+            let key_values = [
+                serde_json::json!(key.tx_hash),
+                serde_json::json!(key.msg_index),
+                serde_json::json!(key.event_index),
+            ];
+            columns.extend([
+                TX_HASH_COLUMN_NAME,
+                MSG_INDEX_COLUMN_NAME,
+                EVENT_INDEX_COLUMN_NAME,
+            ]);
+            values.extend(key_values.iter());
+            db_id = (*persister)
+                .save_unique(table_name, &columns[..], &values[..])
+                .await?
+                .unwrap_or_default();
+        } else {
             db_id = (*persister)
                 .save(table_name, &columns[..], &values[..], record_id)
                 .await?
@@ -6,7 +6,7 @@ use core::fmt::Debug;
 use log::debug;
 use sea_orm::entity::prelude::*;
 use sea_orm::sea_query::{Alias, Expr, IntoIden, Query};
-use sea_orm::{ConnectionTrait, DatabaseConnection, JsonValue, Value};
+use sea_orm::{ConnectionTrait, DatabaseConnection, JsonValue, Statement, Value};
 use serde::{Deserialize, Serialize};
 use std::iter::Iterator;
 
@@ -67,6 +67,34 @@ impl DatabasePersister {
     pub fn into_sql(&self) -> String {
         "".to_string()
     }
+
+    fn insert_statement(
+        &self,
+        table_name: &str,
+        column_names: &[&str],
+        values: &[&JsonValue],
+    ) -> Result<Statement> {
+        let mut insert_columns = vec![];
+        let mut vals = vec![];
+        for (value_index, column_name) in column_names.iter().enumerate() {
+            vals.push(db_value(values[value_index]));
+            insert_columns.push(Alias::new(&db_column_name(column_name)).into_iden());
+        }
+        let stmt = Query::insert()
+            .into_table(Alias::new(&db_table_name(table_name)))
+            .columns(insert_columns)
+            .values(vals)?
+            .to_owned();
+        Ok(self.db.get_database_backend().build(&stmt))
+    }
+}
+
+fn db_value(input_val: &JsonValue) -> Value {
+    match input_val {
+        JsonValue::String(_v) => Datatype::String.value_with_datatype(Some(input_val)),
+        JsonValue::Number(_v) => Datatype::BigInt.value_with_datatype(Some(input_val)),
+        _ => Datatype::String.value_with_datatype(Some(input_val)),
+    }
 }
 
 #[async_trait]
@@ -84,53 +112,46 @@ impl Persister for DatabasePersister {
             "saving table_name:{}, column_names:{:#?}, values:{:#?}, id:{:?}, db:{:?}",
             table_name, column_names, values, id, db
         );
-        let mut update = false;
-        let mut cols = vec![];
-        let mut insert_columns = vec![];
-        let mut vals = vec![];
-        if id.is_some() {
-            update = true;
-        }
-        for (value_index, column_name) in column_names.iter().enumerate() {
-            let input_val = values[value_index];
-            let val = match input_val {
-                JsonValue::String(_v) => Datatype::String.value_with_datatype(Some(input_val)),
-                JsonValue::Number(_v) => Datatype::BigInt.value_with_datatype(Some(input_val)),
-                _ => Datatype::String.value_with_datatype(Some(input_val)),
-            };
-            let column_ident = Alias::new(&db_column_name(column_name)).into_iden();
-            if update {
-                cols.push((column_ident, val));
-            } else {
-                vals.push(val);
-                insert_columns.push(column_ident);
+        if let Some(id) = id {
+            let mut cols = vec![];
+            for (value_index, column_name) in column_names.iter().enumerate() {
+                let column_ident = Alias::new(&db_column_name(column_name)).into_iden();
+                cols.push((column_ident, db_value(values[value_index])));
             }
-        }
-
-        let builder = db.get_database_backend();
-
-        if update {
+            let builder = db.get_database_backend();
             let stmt = Query::update()
                 .table(Alias::new(&db_table_name(table_name)))
                 .values(cols)
-                .and_where(
-                    Expr::col(Alias::new(DEFAULT_ID_COLUMN_NAME).into_iden())
-                        .eq::<u64>(id.unwrap()),
-                )
+                .and_where(Expr::col(Alias::new(DEFAULT_ID_COLUMN_NAME).into_iden()).eq::<u64>(id))
                 .to_owned();
 
             let result = db.execute(builder.build(&stmt)).await?;
             Ok(result.last_insert_id())
         } else {
-            let stmt = Query::insert()
-                .into_table(Alias::new(&db_table_name(table_name)))
-                .columns(insert_columns)
-                .values(vals)?
-                .to_owned();
-            let result = db.execute(builder.build(&stmt)).await?;
+            let statement = self.insert_statement(table_name, column_names, values)?;
+            let result = db.execute(statement).await?;
             Ok(result.last_insert_id())
         }
     }
+
+    async fn save_unique<'a>(
+        &'a self,
+        table_name: &'a str,
+        column_names: &'a [&'a str],
+        values: &'a [&'a JsonValue],
+    ) -> Result<Option<Self::Id>> {
+        debug!(
+            "saving unique table_name:{}, column_names:{:#?}, values:{:#?}",
+            table_name, column_names, values
+        );
+        let mut statement = self.insert_statement(table_name, column_names, values)?;
+        statement.sql = format!("{} ON CONFLICT DO NOTHING", statement.sql);
+        let result = self.db.execute(statement).await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(result.last_insert_id()))
+    }
 }
 
 #[cfg(test)]
@@ -174,4 +195,48 @@ pub mod tests {
         assert_eq!(expected_log, log);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_unique_persistence() -> anyhow::Result<()> {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 15,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 0,
+                },
+            ])
+            .into_connection();
+        let persister = DatabasePersister::new(db);
+        let values: &[&serde_json::Value] = &[&json!("Gavin"), &json!("ABCDEF"), &json!(0)];
+        let columns = &["first_name", "tx_hash", "msg_index"];
+        assert_eq!(
+            Some(15),
+            persister.save_unique("Contact", columns, values).await?
+        );
+        // The second save conflicts with the first and is skipped.
+        assert_eq!(
+            None,
+            persister.save_unique("Contact", columns, values).await?
+        );
+        let expected_sql = r#"INSERT INTO "contact" ("first_name", "tx_hash", "msg_index") VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"#;
+        let expected_values = vec!["Gavin".into(), "ABCDEF".into(), 0_i64.into()];
+        let expected_log = vec![
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                expected_sql,
+                expected_values.clone(),
+            ),
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                expected_sql,
+                expected_values,
+            ),
+        ];
+        assert_eq!(expected_log, persister.into_transaction_log());
+        Ok(())
+    }
 }
@@ -3,6 +3,9 @@ use convert_case::{Case, Casing};
 pub static DEFAULT_ID_COLUMN_NAME: &str = "id";
 pub static DEFAULT_TABLE_NAME_COLUMN_NAME: &str = "target_table_name";
 pub static TARGET_ID_COLUMN_NAME: &str = "target_id";
+pub static TX_HASH_COLUMN_NAME: &str = "tx_hash";
+pub static MSG_INDEX_COLUMN_NAME: &str = "msg_index";
+pub static EVENT_INDEX_COLUMN_NAME: &str = "event_index";
 
 fn db_normalize(input: &str) -> String {
     input.to_case(Case::Snake)
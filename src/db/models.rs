@@ -1,4 +1,7 @@
-use super::schema::{block, contracts, cw20_balances, dao, gov_token, transaction};
+use super::schema::{
+    bank_transfers, block, contract_history, contracts, cw20_balances, dao, delegation_ledger,
+    gov_deposit, gov_token, gov_vote, ibc_transfer, transaction,
+};
 use bigdecimal::BigDecimal; // Has to match diesel's version!
 use cosmrs::cosmwasm::MsgInstantiateContract;
 use cw3_dao::msg::GovTokenInstantiateMsg;
@@ -43,6 +46,20 @@ impl<'a> NewContract<'a> {
     }
 }
 
+/// A change to a contract's code or admin.
+#[derive(Insertable, Debug)]
+#[table_name = "contract_history"]
+pub struct NewContractHistory<'a> {
+    pub contract_address: &'a str,
+    pub operation: &'a str,
+    pub code_id: Option<i64>,
+    pub admin: Option<&'a str>,
+    pub sender: &'a str,
+    pub height: i64,
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+}
+
 // TODO(gavin.doughtie): These are out of date and we're just
 // using the diesel fields directly right now, but it's going
 // to be better to move to these structs in the long run so
@@ -133,6 +150,22 @@ impl<'a> NewMultisig<'a> {
     }
 }
 
+/// One coin moved from one account to another.
+#[derive(Insertable, Debug)]
+#[table_name = "bank_transfers"]
+pub struct NewBankTransfer<'a> {
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+    pub event_index: i32,
+    pub height: i64,
+    pub from_address: &'a str,
+    pub to_address: &'a str,
+    pub denom: &'a str,
+    pub amount: BigDecimal,
+    pub from_dao_address: Option<&'a str>,
+    pub to_dao_address: Option<&'a str>,
+}
+
 #[derive(Insertable)]
 #[table_name = "cw20_balances"]
 pub struct NewCw20Balance<'a> {
@@ -165,6 +198,72 @@ pub struct Cw20 {
     pub marketing_id: Option<i32>,
 }
 
+/// One coin of a change to a delegation.
+#[derive(Insertable, Debug)]
+#[table_name = "delegation_ledger"]
+pub struct NewDelegationLedgerEntry<'a> {
+    pub delegator: &'a str,
+    pub validator: &'a str,
+    pub operation: &'a str,
+    pub denom: &'a str,
+    pub amount: BigDecimal,
+    pub completion_time: Option<&'a str>,
+    pub height: i64,
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+    pub event_index: i32,
+}
+
+/// An ICS-20 token transfer into or out of the chain.
+#[derive(Insertable, Debug)]
+#[table_name = "ibc_transfer"]
+pub struct NewIbcTransfer<'a> {
+    pub direction: &'a str,
+    pub source_port: &'a str,
+    pub source_channel: &'a str,
+    pub sequence: i64,
+    pub destination_port: &'a str,
+    pub destination_channel: &'a str,
+    pub sender: &'a str,
+    pub receiver: &'a str,
+    pub denom: String,
+    pub denom_trace: &'a str,
+    pub base_denom: &'a str,
+    pub amount: BigDecimal,
+    pub status: &'a str,
+    pub error: Option<String>,
+    pub height: i64,
+    pub tx_hash: Option<String>,
+}
+
+/// One coin deposited on a chain governance proposal.
+#[derive(Insertable, Debug)]
+#[table_name = "gov_deposit"]
+pub struct NewGovDeposit<'a> {
+    pub proposal_id: i64,
+    pub depositor: &'a str,
+    pub denom: &'a str,
+    pub amount: BigDecimal,
+    pub height: i64,
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+    pub event_index: i32,
+}
+
+/// One option of a vote on a chain governance proposal.
+#[derive(Insertable, Debug)]
+#[table_name = "gov_vote"]
+pub struct NewGovVote<'a> {
+    pub proposal_id: i64,
+    pub voter: &'a str,
+    pub option: &'a str,
+    pub weight: BigDecimal,
+    pub height: i64,
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+    pub event_index: i32,
+}
+
 #[derive(Insertable)]
 #[table_name = "gov_token"]
 pub struct NewGovToken<'a> {
@@ -197,6 +296,7 @@ pub struct NewBlock<'a> {
     pub height: i64,
     pub hash: &'a str,
     pub num_txs: i64,
+    pub time: String,
     // pub total_gas: i64,
     // pub proposer_address: &'a str,
 }
@@ -207,6 +307,7 @@ impl<'a> NewBlock<'a> {
             height: block.header.height.value() as i64,
             hash,
             num_txs: block.data.iter().len() as i64,
+            time: block.header.time.to_rfc3339(),
         }
     }
 }
@@ -216,6 +317,7 @@ pub struct Block {
     pub height: i64,
     pub hash: String,
     pub num_txs: Option<i64>,
+    pub time: Option<String>,
 }
 
 #[derive(Queryable)]
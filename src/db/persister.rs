@@ -21,6 +21,20 @@ pub trait Persister: Send + Sync + std::fmt::Debug {
         id: Option<Self::Id>,
     ) -> Result<Self::Id>;
 
+    /// Inserts a record whose columns include a natural key, skipping it if
+    /// a record with the same key was already saved. Returns None when the
+    /// record was skipped.
+    async fn save_unique<'a>(
+        &'a self,
+        table_name: &'a str,
+        column_names: &'a [&'a str],
+        values: &'a [&'a Value],
+    ) -> Result<Option<Self::Id>> {
+        self.save(table_name, column_names, values, None)
+            .await
+            .map(Some)
+    }
+
     fn get_db(&mut self) -> Option<&DatabaseConnection> {
         None
     }
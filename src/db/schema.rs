@@ -1,8 +1,41 @@
+table! {
+    authz_grant (id) {
+        id -> Int4,
+        granter -> Text,
+        grantee -> Text,
+        msg_type_url -> Text,
+        authorization_type_url -> Text,
+        expiration -> Nullable<Text>,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+        revoked_height -> Nullable<Int8>,
+        revoked_tx_hash -> Nullable<Text>,
+    }
+}
+
+table! {
+    bank_transfers (id) {
+        id -> Int4,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+        event_index -> Int4,
+        height -> Int8,
+        from_address -> Text,
+        to_address -> Text,
+        denom -> Text,
+        amount -> Numeric,
+        from_dao_address -> Nullable<Text>,
+        to_dao_address -> Nullable<Text>,
+    }
+}
+
 table! {
     block (height) {
         height -> Int8,
         hash -> Text,
         num_txs -> Nullable<Int8>,
+        time -> Nullable<Text>,
     }
 }
 
@@ -12,6 +45,7 @@ table! {
         creator -> Text,
         creation_time -> Text,
         height -> Int8,
+        checksum -> Nullable<Text>,
     }
 }
 
@@ -21,6 +55,20 @@ table! {
     }
 }
 
+table! {
+    contract_history (id) {
+        id -> Int4,
+        contract_address -> Text,
+        operation -> Text,
+        code_id -> Nullable<Int8>,
+        admin -> Nullable<Text>,
+        sender -> Text,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+    }
+}
+
 table! {
     contracts (address) {
         address -> Text,
@@ -51,6 +99,9 @@ table! {
         recipient_address -> Text,
         amount -> Numeric,
         height -> Numeric,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Nullable<Int4>,
+        event_index -> Nullable<Int4>,
     }
 }
 
@@ -66,6 +117,22 @@ table! {
     }
 }
 
+table! {
+    delegation_ledger (id) {
+        id -> Int4,
+        delegator -> Text,
+        validator -> Text,
+        operation -> Text,
+        denom -> Text,
+        amount -> Numeric,
+        completion_time -> Nullable<Text>,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+        event_index -> Int4,
+    }
+}
+
 table! {
     exec_msg (id) {
         id -> Int4,
@@ -74,6 +141,58 @@ table! {
     }
 }
 
+table! {
+    failed_tx_search (id) {
+        id -> Int4,
+        start_height -> Int8,
+        end_height -> Int8,
+        query -> Text,
+        page -> Int4,
+        attempts -> Int4,
+        error -> Nullable<Text>,
+        failed_at -> Timestamp,
+    }
+}
+
+table! {
+    failed_transaction (hash) {
+        hash -> Text,
+        height -> Int8,
+        tx_index -> Int4,
+        code -> Int4,
+        codespace -> Text,
+        log -> Text,
+        recorded_at -> Timestamp,
+    }
+}
+
+table! {
+    gov_deposit (id) {
+        id -> Int4,
+        proposal_id -> Int8,
+        depositor -> Text,
+        denom -> Text,
+        amount -> Numeric,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+        event_index -> Int4,
+    }
+}
+
+table! {
+    gov_proposal (proposal_id) {
+        proposal_id -> Int8,
+        proposer -> Text,
+        content_type_url -> Text,
+        title -> Text,
+        description -> Text,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        voting_start_height -> Nullable<Int8>,
+    }
+}
+
 table! {
     gov_token (address) {
         address -> Text,
@@ -84,6 +203,52 @@ table! {
     }
 }
 
+table! {
+    gov_vote (id) {
+        id -> Int4,
+        proposal_id -> Int8,
+        voter -> Text,
+        option -> Text,
+        weight -> Numeric,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        msg_index -> Int4,
+        event_index -> Int4,
+    }
+}
+
+table! {
+    ibc_transfer (id) {
+        id -> Int4,
+        direction -> Text,
+        source_port -> Text,
+        source_channel -> Text,
+        sequence -> Int8,
+        destination_port -> Text,
+        destination_channel -> Text,
+        sender -> Text,
+        receiver -> Text,
+        denom -> Text,
+        denom_trace -> Text,
+        base_denom -> Text,
+        amount -> Numeric,
+        status -> Text,
+        error -> Nullable<Text>,
+        height -> Int8,
+        tx_hash -> Nullable<Text>,
+        resolved_height -> Nullable<Int8>,
+        resolved_tx_hash -> Nullable<Text>,
+    }
+}
+
+table! {
+    indexed_block_range (start_height, end_height) {
+        start_height -> Int8,
+        end_height -> Int8,
+        indexed_at -> Timestamp,
+    }
+}
+
 table! {
     logo (id) {
         id -> Int4,
@@ -103,6 +268,14 @@ table! {
     }
 }
 
+table! {
+    sync_state (name) {
+        name -> Text,
+        height -> Int8,
+        updated_at -> Timestamp,
+    }
+}
+
 table! {
     transaction (hash) {
         hash -> Text,
@@ -112,16 +285,28 @@ table! {
 }
 
 allow_tables_to_appear_in_same_query!(
+    authz_grant,
+    bank_transfers,
     block,
     codes,
     coin,
+    contract_history,
     contracts,
     cw20_balances,
     cw20_transactions,
     dao,
+    delegation_ledger,
     exec_msg,
+    failed_transaction,
+    failed_tx_search,
+    gov_deposit,
+    gov_proposal,
     gov_token,
+    gov_vote,
+    ibc_transfer,
+    indexed_block_range,
     logo,
     marketing,
+    sync_state,
     transaction,
 );
@@ -0,0 +1,116 @@
+use crate::config::IndexerConfig;
+use crate::metrics::indexed_height;
+use diesel::pg::PgConnection;
+use diesel::Connection;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tendermint_rpc::{Client, HttpClient};
+
+// How long readiness waits on each RPC node's status.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+static LAST_PROGRESS: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+/// Records that the indexer did something: an RPC request succeeded, a
+/// transaction was indexed, or the live listener is waiting on a chain it
+/// can still reach.
+pub fn record_progress() {
+    *LAST_PROGRESS.lock().unwrap() = Instant::now();
+}
+
+pub fn since_last_progress() -> Duration {
+    LAST_PROGRESS.lock().unwrap().elapsed()
+}
+
+/// What `/healthz` and `/readyz` check, taken from the indexer's config so
+/// they can be answered from the HTTP server's own task.
+#[derive(Clone, Debug)]
+pub struct HealthChecks {
+    liveness_timeout: Duration,
+    readiness_max_lag: u64,
+    rpc_urls: Vec<String>,
+    database_url: Option<String>,
+}
+
+impl HealthChecks {
+    pub fn from_config(config: &IndexerConfig) -> Self {
+        HealthChecks {
+            liveness_timeout: Duration::from_secs(config.liveness_timeout_seconds),
+            readiness_max_lag: config.readiness_max_lag,
+            rpc_urls: config.tendermint_rpc_urls.clone(),
+            database_url: if config.postgres_backend {
+                Some(config.database_url.clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Live as long as the indexer has made progress within the liveness
+    /// timeout.
+    pub fn check_liveness(&self) -> anyhow::Result<()> {
+        let idle = since_last_progress();
+        if idle > self.liveness_timeout {
+            return Err(anyhow::anyhow!("no progress for {:?}", idle));
+        }
+        Ok(())
+    }
+
+    /// Ready once the database and an RPC node are reachable and the indexer
+    /// is within the configured number of blocks of the chain head.
+    pub async fn check_readiness(&self) -> anyhow::Result<()> {
+        if let Some(database_url) = self.database_url.clone() {
+            tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+                let db = PgConnection::establish(&database_url)?;
+                db.execute("SELECT 1")?;
+                Ok(())
+            })
+            .await?
+            .map_err(|e| e.context("database unreachable"))?;
+        }
+        let chain_head = self.chain_head().await?;
+        let height = indexed_height();
+        let lag = chain_head.saturating_sub(height);
+        if lag > self.readiness_max_lag {
+            return Err(anyhow::anyhow!(
+                "at block {}, {} blocks behind chain head {}",
+                height,
+                lag,
+                chain_head
+            ));
+        }
+        Ok(())
+    }
+
+    /// The highest block reported by any reachable RPC node.
+    pub async fn chain_head(&self) -> anyhow::Result<u64> {
+        let mut chain_head = None;
+        for url in self.rpc_urls.iter() {
+            let client = HttpClient::new::<&str>(url)?;
+            if let Ok(Ok(status)) = tokio::time::timeout(STATUS_TIMEOUT, client.status()).await {
+                let height = status.sync_info.latest_block_height.value();
+                chain_head = Some(chain_head.unwrap_or_default().max(height));
+            }
+        }
+        chain_head.ok_or_else(|| anyhow::anyhow!("no RPC node reachable"))
+    }
+}
+
+#[test]
+fn test_liveness() {
+    let checks = HealthChecks {
+        liveness_timeout: Duration::from_secs(60),
+        readiness_max_lag: 10,
+        rpc_urls: vec![],
+        database_url: None,
+    };
+    record_progress();
+    assert!(checks.check_liveness().is_ok());
+    let stalled = HealthChecks {
+        liveness_timeout: Duration::ZERO,
+        ..checks
+    };
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(stalled.check_liveness().is_err());
+}
@@ -1,48 +1,30 @@
-use crate::config::IndexerConfig;
-use crate::indexing::event_map::EventMap;
+use crate::block_parser::fetch_block_range_blocks;
+use crate::config::{IndexerConfig, IngestionMode};
 use crate::indexing::indexer_registry::IndexerRegistry;
 use crate::indexing::msg_set::MsgSet;
-use crate::indexing::tx::{process_parsed, process_parsed_v1beta};
+use crate::metrics::{record_indexed_height, BLOCKS_PROCESSED, TARGET_HEIGHT};
+use crate::rpc::client_pool::ClientPool;
+use crate::shutdown::ShutdownSignal;
+use crate::source::{index_tx, SourceTx};
+use crate::util::failed_requests::record_failed_request;
+use crate::util::height_ranges::{merge_ranges, missing_ranges, HeightRange};
 use crate::util::query_stream::{QueryStream, TxSearchRequest};
-use crate::util::transaction_util::insert_transaction;
-use cosmos_sdk_proto::cosmos::tx::v1beta1::Tx as TxV1;
-use cosmrs::tx::Tx;
-use futures::future::join_all;
+use crate::util::sync_state::{
+    get_indexed_ranges, get_sync_height, record_indexed_range, set_sync_height, SyncCursor,
+    HISTORICAL_SYNC,
+};
+use futures::future::{join_all, ready};
+use futures::stream::{self, StreamExt};
 use futures::FutureExt;
 use log::{debug, error, info, warn};
 use math::round;
-use prost::Message;
 use std::cmp::min;
-use std::collections::BTreeMap;
-use tendermint::abci::responses::Event;
+use std::fmt;
+use std::time::Duration;
 use tendermint_rpc::endpoint::tx::Response;
 use tendermint_rpc::endpoint::tx_search::Response as TxSearchResponse;
 use tendermint_rpc::query::Query;
-use tendermint_rpc::Client;
-use tendermint_rpc::HttpClient as TendermintClient;
 use tokio::sync::Mutex;
-use tokio_stream::StreamExt;
-
-// This is a tech debut function that maps events into a structure
-// that's a little easier to index.
-fn map_from_events(events: &[Event], event_map: &mut EventMap) -> anyhow::Result<()> {
-    for event in events {
-        let event_name = &event.type_str;
-        for attribute in &event.attributes {
-            let event_key = format!("{}.{}", event_name, attribute.key);
-            let attributes = if let Some(existing_attributes) = event_map.get_mut(&event_key) {
-                existing_attributes
-            } else {
-                event_map.insert(event_key.clone(), vec![]);
-                event_map
-                    .get_mut(&event_key)
-                    .ok_or_else(|| anyhow::anyhow!("no attribute {} found", event_key))?
-            };
-            attributes.push(attribute.value.to_string());
-        }
-    }
-    Ok(())
-}
 
 // Generic driver function for "put these blockchain transactions into the index".
 // This function isn't actually async, but may eventually be
@@ -68,53 +50,21 @@ pub fn index_search_result(
     config: &IndexerConfig,
     msg_set: MsgSet,
 ) -> anyhow::Result<()> {
-    if config.write_transactions_in_database {
-        insert_transaction(tx_response, registry)?;
-    }
-    let mut events = BTreeMap::default();
-    let block_height = tx_response.height;
-    map_from_events(&tx_response.tx_result.events, &mut events)?;
-    if events.get("tx.height").is_none() {
-        events.insert("tx.height".to_string(), vec![block_height.to_string()]);
-    }
-    match Tx::from_bytes(tx_response.tx.as_bytes()) {
-        Ok(unmarshalled_tx) => {
-            if let Err(e) = process_parsed(registry, &unmarshalled_tx, &events, msg_set) {
-                error!("Error in process_parsed: {:?}\n{:?}", e, unmarshalled_tx);
-            }
-        }
-        Err(e) => {
-            warn!(
-                "Error unmarshalling: {:?} via Tx::from_bytes, trying v1beta decode",
-                e
-            );
-            info!("tx_response:\n{:?}", tx_response);
-            match TxV1::decode(tx_response.tx.as_bytes()) {
-                // match TxV1::decode(tx_response.tx.as_bytes()) {
-                Ok(unmarshalled_tx) => {
-                    info!("decoded response debug:\n{:?}", unmarshalled_tx);
-                    if let Err(e) =
-                        process_parsed_v1beta(registry, &unmarshalled_tx, &events, msg_set)
-                    {
-                        error!("Error in process_parsed: {:?}", e);
-                    }
-                }
-                Err(e) => {
-                    error!("Error decoding: {:?}", e);
-                }
-            }
-        }
-    }
-    Ok(())
+    index_tx(
+        &SourceTx::from_response(tx_response)?,
+        registry,
+        config,
+        msg_set,
+    )
 }
 
 async fn requeue(
-    config: &IndexerConfig,
     queries_mutex: &Mutex<QueryStream>,
     tx_request: Box<TxSearchRequest>,
+    delay: Duration,
 ) -> anyhow::Result<()> {
-    if config.requeue_sleep > 0 {
-        std::thread::sleep(std::time::Duration::from_millis(config.requeue_sleep));
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
     }
     debug!("Requeing tx_request {:?}", &tx_request);
     let mut queries = queries_mutex.lock().await;
@@ -122,18 +72,16 @@ async fn requeue(
     Ok(())
 }
 
-// Process the response from calling an RPC. This can result in additional
-// RPC calls being queued.
-#[allow(clippy::too_many_arguments)]
+// Process the response from calling an RPC, collecting its transactions in
+// `fetched`. This can result in additional RPC calls being queued.
 async fn handle_transaction_response(
-    response: Result<TxSearchResponse, tendermint_rpc::error::Error>,
+    response: anyhow::Result<TxSearchResponse>,
     tx_request: Box<TxSearchRequest>,
     current_height: u64,
     last_block: u64,
-    registry: &IndexerRegistry,
     config: &IndexerConfig,
-    msg_set: MsgSet,
     queries_mutex: &Mutex<QueryStream>,
+    fetched: &Mutex<Vec<Response>>,
 ) -> anyhow::Result<()> {
     match response {
         Ok(search_results) => {
@@ -142,8 +90,11 @@ async fn handle_transaction_response(
             if total_count == 0 && tx_request.page == 1 {
                 // There was no error, and no results. Look again.
                 if tx_request.reque_count < config.max_empty_block_retries as i64 {
-                    return requeue(config, queries_mutex, tx_request).await;
+                    let delay = Duration::from_millis(config.requeue_sleep);
+                    return requeue(queries_mutex, tx_request, delay).await;
                 } else {
+                    // Ranges without any transactions are common; this one
+                    // is indexed, there was just nothing in it.
                     warn!(
                         "Received empty results for request {:#?}/{:#?} after {} retries",
                         &tx_request, &tx_request.query, tx_request.reque_count
@@ -186,43 +137,204 @@ async fn handle_transaction_response(
                     queries.enqueue(Box::new(tx_search));
                 }
             }
-            index_search_results(search_results, registry, config, msg_set.clone()).await?;
+            fetched.lock().await.extend(search_results.txs);
         }
         Err(e) => {
             debug!(
                 "Error: {:?}\npage: {}, current_height:{}",
                 e, tx_request.page, current_height
             );
-            return requeue(config, queries_mutex, tx_request).await;
+            let mut tx_request = tx_request;
+            if tx_request.record_failure(e.to_string(), config.max_request_retries) {
+                let delay = tx_request.retry_delay(config.retry_base_sleep, config.max_retry_sleep);
+                debug!(
+                    "Retrying page {}, blocks {}-{} in {:?} (failures: {})",
+                    tx_request.page, current_height, last_block, delay, tx_request.failures
+                );
+                return requeue(queries_mutex, tx_request, delay).await;
+            }
+            warn!(
+                "Giving up on page {}, blocks {}-{} after {} failures",
+                tx_request.page, current_height, last_block, tx_request.failures
+            );
+            let error = anyhow::anyhow!(
+                "page {} for blocks {}-{} failed after {} attempts: {:?}",
+                tx_request.page,
+                current_height,
+                last_block,
+                tx_request.failures,
+                e
+            );
+            queries_mutex.lock().await.fail(tx_request);
+            return Err(error);
         }
     }
     Ok(())
 }
 
+// The last block (inclusive) loaded by a load_block_transactions call
+// starting at `current_height`.
+fn last_block_for_page(config: &IndexerConfig, current_height: u64) -> u64 {
+    let last_block = current_height + config.block_page_size;
+    if config.tendermint_final_block > 0 {
+        min(last_block, config.tendermint_final_block)
+    } else {
+        last_block
+    }
+}
+
+// Where historical indexing should begin: the configured initial block, or
+// just past the persisted sync cursor if that is further along.
+fn initial_sync_height(registry: &IndexerRegistry, config: &IndexerConfig) -> anyhow::Result<u64> {
+    let mut initial_height = config.tendermint_initial_block;
+    if config.tendermint_force_initial_block {
+        info!("Forcing historical sync to start at {}", initial_height);
+        return Ok(initial_height);
+    }
+    if let Some(db) = &registry.db {
+        if let Some(synced_height) = get_sync_height(db, HISTORICAL_SYNC)? {
+            if synced_height + 1 > initial_height {
+                info!(
+                    "Resuming historical sync after previously indexed height {}",
+                    synced_height
+                );
+                initial_height = synced_height + 1;
+            }
+        }
+    }
+    Ok(initial_height)
+}
+
+// Records the outcome of a batch of load_block_transactions calls,
+// remembering which ranges finished, and persists the cursor if it moved.
+// Returns the ranges that failed.
+fn complete_block_batch(
+    registry: &IndexerRegistry,
+    cursor: &mut SyncCursor,
+    results: Vec<(HeightRange, anyhow::Result<()>)>,
+) -> anyhow::Result<Vec<HeightRange>> {
+    let mut advanced = false;
+    let mut failed_ranges = vec![];
+    for (range, result) in results {
+        match result {
+            Ok(()) => {
+                if let Some(db) = &registry.db {
+                    record_indexed_range(db, &range)?;
+                }
+                BLOCKS_PROCESSED.inc_by(range.len());
+                advanced |= cursor.complete(range.start, range.end);
+            }
+            Err(e) => {
+                error!("Failed loading transactions for blocks {}: {:?}", range, e);
+                failed_ranges.push(range);
+            }
+        }
+    }
+    if advanced {
+        if let Some(db) = &registry.db {
+            set_sync_height(db, HISTORICAL_SYNC, cursor.height())?;
+        }
+        record_indexed_height(cursor.height());
+        debug!("historical sync cursor at {}", cursor.height());
+    }
+    Ok(failed_ranges)
+}
+
+// Logs the failed pages in a batch and returns how many there were.
+fn count_failed_pages(results: Vec<anyhow::Result<()>>) -> usize {
+    let mut failed_pages = 0;
+    for result in results {
+        if let Err(e) = result {
+            error!("Failed loading transaction page: {:?}", e);
+            failed_pages += 1;
+        }
+    }
+    failed_pages
+}
+
 pub async fn load_block_transactions(
-    tendermint_client: &TendermintClient,
+    clients: &ClientPool,
     config: &IndexerConfig,
     registry: &IndexerRegistry,
     msg_set: MsgSet,
     current_height: u64,
 ) -> anyhow::Result<()> {
-    let mut last_block = current_height + config.block_page_size;
-    if config.tendermint_final_block > 0 {
-        last_block = min(last_block, config.tendermint_final_block);
+    let range = HeightRange::new(current_height, last_block_for_page(config, current_height));
+    load_block_range_transactions(clients, config, registry, msg_set, range).await
+}
+
+// Loads and indexes every transaction in `range`, returning an error if any
+// page of results could not be loaded or indexed.
+pub async fn load_block_range_transactions(
+    clients: &ClientPool,
+    config: &IndexerConfig,
+    registry: &IndexerRegistry,
+    msg_set: MsgSet,
+    range: HeightRange,
+) -> anyhow::Result<()> {
+    let shutdown = ShutdownSignal::never();
+    let txs = fetch_block_range_transactions(clients, config, registry, range, &shutdown).await?;
+    index_in_order(registry, config, msg_set, txs, None)?;
+    Ok(())
+}
+
+/// Sorts `txs` into (height, tx index) order, dropping duplicates and any
+/// transaction at or before `after`.
+pub fn order_transactions(mut txs: Vec<Response>, after: Option<(u64, u32)>) -> Vec<Response> {
+    let position = |tx: &Response| (tx.height.value(), tx.index);
+    txs.sort_by_key(position);
+    txs.dedup_by_key(|tx| position(tx));
+    if let Some(after) = after {
+        txs.retain(|tx| position(tx) > after);
     }
-    info!(
-        "loading transactions for blocks {}-{}",
-        current_height, last_block
-    );
+    txs
+}
+
+/// Indexes `txs` strictly in (height, tx index) order, skipping those at or
+/// before `after` (consecutive ranges share a block). Returns the position
+/// of the last transaction indexed.
+pub fn index_in_order(
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    txs: Vec<Response>,
+    after: Option<(u64, u32)>,
+) -> anyhow::Result<Option<(u64, u32)>> {
+    let mut last_indexed = after;
+    for tx_response in order_transactions(txs, after) {
+        index_search_result(&tx_response, registry, config, msg_set.clone())?;
+        last_indexed = Some((tx_response.height.value(), tx_response.index));
+    }
+    Ok(last_indexed)
+}
+
+// Fetches every transaction in `range` with tx_search, without indexing
+// anything, returning an error if any page of results could not be loaded.
+// On shutdown no further pages are requested and the range is abandoned.
+pub async fn fetch_block_range_transactions(
+    clients: &ClientPool,
+    config: &IndexerConfig,
+    registry: &IndexerRegistry,
+    range: HeightRange,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<Response>> {
+    let current_height = range.start;
+    let last_block = range.end;
+    info!("loading transactions for blocks {}", range);
     let key = "tx.height";
     let query = Query::gte(key, current_height).and_lt(key, last_block + 1);
     let page_one_request = TxSearchRequest::from_query_and_page(query, 1);
     let mut queries = QueryStream::new();
     queries.enqueue(Box::new(page_one_request));
     let queries_mutex = Mutex::from(queries);
+    let fetched = Mutex::from(vec![]);
     let mut page_futures = vec![];
+    let mut failed_pages = 0;
     let max_requests = config.max_requests as usize;
     loop {
+        if shutdown.is_shutdown() {
+            return Err(anyhow::anyhow!("abandoned blocks {} to shut down", range));
+        }
         let mut query = None;
         let mut page = 0;
         let mut tx_request = None;
@@ -244,13 +356,13 @@ pub async fn load_block_transactions(
                     tx_request.page, tx_request.reque_count
                 );
             }
-            let f = tendermint_client
+            // Only ask nodes that already have every block in the range.
+            let f = clients
                 .tx_search(
                     query.clone(),
-                    false,
                     page,
                     config.transaction_page_size,
-                    tendermint_rpc::Order::Ascending,
+                    last_block,
                 )
                 .map(|response| {
                     handle_transaction_response(
@@ -258,16 +370,15 @@ pub async fn load_block_transactions(
                         tx_request,
                         current_height,
                         last_block,
-                        registry,
                         config,
-                        msg_set.clone(),
                         &queries_mutex,
+                        &fetched,
                     )
                 });
             page_futures.push(f);
             if page_futures.len() == max_requests || requeue_count > 0 {
                 let results_futures = join_all(page_futures).await;
-                join_all(results_futures).await;
+                failed_pages += count_failed_pages(join_all(results_futures).await);
                 page_futures = vec![];
             }
         } else {
@@ -278,64 +389,150 @@ pub async fn load_block_transactions(
             if results_futures.is_empty() {
                 break;
             }
-            join_all(results_futures).await;
+            failed_pages += count_failed_pages(join_all(results_futures).await);
             page_futures = vec![];
         }
     }
-    Ok(())
+    let failed_requests = queries_mutex.lock().await.take_failed();
+    if let Some(db) = &registry.db {
+        for failed_request in failed_requests.iter() {
+            if let Err(e) = record_failed_request(db, &range, failed_request) {
+                error!(
+                    "Error recording failed request {:?}: {:?}",
+                    failed_request, e
+                );
+            }
+        }
+    }
+    if failed_pages > 0 {
+        return Err(anyhow::anyhow!(
+            "{} pages failed for blocks {}",
+            failed_pages,
+            range
+        ));
+    }
+    Ok(fetched.into_inner())
 }
 
-pub async fn block_synchronizer(
+/// Fetches the transactions in `range` with the configured ingestion mode.
+pub async fn fetch_range(
+    clients: &ClientPool,
+    config: &IndexerConfig,
+    registry: &IndexerRegistry,
+    range: HeightRange,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<Response>> {
+    match config.ingestion_mode {
+        IngestionMode::TxSearch => {
+            fetch_block_range_transactions(clients, config, registry, range, shutdown).await
+        }
+        IngestionMode::Blocks => {
+            fetch_block_range_blocks(clients, config, registry, range, shutdown).await
+        }
+    }
+}
+
+// Fetches `ranges` concurrently, up to the reorder window at a time, and
+// indexes their transactions strictly in height order as they become
+// available, recording each range as it completes. A range that fails is
+// skipped (and left for backfill) without holding up the ones after it.
+// On shutdown no further ranges are started, and ranges still being fetched
+// are abandoned. Returns the ranges that failed.
+async fn sync_ranges(
+    clients: &ClientPool,
     registry: &IndexerRegistry,
     config: &IndexerConfig,
     msg_set: MsgSet,
-) -> anyhow::Result<()> {
-    info!("Loading RPC client for {}", &config.tendermint_rpc_url);
-    let tendermint_client = TendermintClient::new::<&str>(&config.tendermint_rpc_url)?;
-    info!("Waiting for healthy RPC node...");
-    tendermint_client
-        .wait_until_healthy(std::time::Duration::from_millis(1000))
-        .await?;
-    info!("RPC node is healthy, starting historical indexing");
-    let mut latest_block_height = config.tendermint_final_block;
-    if config.tendermint_final_block == 0 {
-        let latest_block_response = tendermint_client.latest_block_results().await?;
-        latest_block_height = latest_block_response.height.value();
+    ranges: Vec<HeightRange>,
+    cursor: &mut SyncCursor,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<HeightRange>> {
+    let mut failed_ranges = vec![];
+    let mut last_indexed = None;
+    let mut last_log_height = ranges.first().map(|range| range.start).unwrap_or_default();
+    let mut fetched_ranges = stream::iter(ranges)
+        .take_while(|_| ready(!shutdown.is_shutdown()))
+        .map(|range| {
+            fetch_range(clients, config, registry, range, shutdown).map(move |txs| (range, txs))
+        })
+        .buffered(config.reorder_window.max(1));
+    while let Some((range, fetched)) = fetched_ranges.next().await {
+        let result = fetched.and_then(|txs| {
+            last_indexed = index_in_order(registry, config, msg_set.clone(), txs, last_indexed)?;
+            Ok(())
+        });
+        failed_ranges.extend(complete_block_batch(
+            registry,
+            cursor,
+            vec![(range, result)],
+        )?);
+        if range.end - last_log_height > 1000 {
+            info!(
+                "indexed blocks at heights {}-{}",
+                last_log_height, range.end
+            );
+            last_log_height = range.end;
+        }
+    }
+    if shutdown.is_shutdown() {
+        info!("historical sync stopped at block {}", cursor.height());
+    }
+    Ok(failed_ranges)
+}
+
+async fn connect_rpc_clients(config: &IndexerConfig) -> anyhow::Result<ClientPool> {
+    info!(
+        "Loading RPC clients for {}",
+        config.tendermint_rpc_urls.join(", ")
+    );
+    ClientPool::connect(config).await
+}
+
+// The configured final block, or the chain head if none was configured.
+async fn final_sync_height(clients: &ClientPool, config: &IndexerConfig) -> anyhow::Result<u64> {
+    if config.tendermint_final_block > 0 {
+        return Ok(config.tendermint_final_block);
     }
+    clients.latest_block_height().await
+}
+
+/// Indexes the blocks from the sync cursor (or configured initial block) up
+/// to the configured final block or the chain head, returning the last
+/// height synchronized. Stops early, with the cursor saved, on `shutdown`.
+pub async fn block_synchronizer(
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<u64> {
+    let clients = connect_rpc_clients(config).await?;
+    info!("RPC nodes are healthy, starting historical indexing");
+    let latest_block_height = final_sync_height(&clients, config).await?;
+    TARGET_HEIGHT.set(latest_block_height as i64);
+    let initial_height = initial_sync_height(registry, config)?;
     info!(
         "synchronizing blocks from {} to {}",
-        config.tendermint_initial_block, latest_block_height
+        initial_height, latest_block_height
     );
-    if latest_block_height < config.tendermint_initial_block {
+    if latest_block_height < initial_height {
         error!(
             "Requested start at {} but latest block height is {}",
-            config.tendermint_initial_block, latest_block_height
+            initial_height, latest_block_height
         );
-        return Ok(());
+        return Ok(latest_block_height);
     }
 
-    let mut current_height = config.tendermint_initial_block;
-    let mut last_log_height = config.tendermint_initial_block;
-    let mut block_transaction_futures = vec![];
-    let max_requests = config.max_requests;
-    let mut outstanding_requests = 0;
+    let mut cursor = SyncCursor::starting_at(initial_height);
+    let mut current_height = initial_height;
+    let mut ranges = vec![];
     while current_height < latest_block_height {
-        // run load_block_transactions calls in in parallel
-        let f = load_block_transactions(
-            &tendermint_client,
-            config,
-            registry,
-            msg_set.clone(),
+        ranges.push(HeightRange::new(
             current_height,
-        );
-        block_transaction_futures.push(f);
-        if current_height - last_log_height > 1000 {
-            info!(
-                "queued blocks at heights {}-{}",
-                last_log_height, current_height
-            );
-            last_log_height = current_height;
-        }
+            min(
+                last_block_for_page(config, current_height),
+                latest_block_height,
+            ),
+        ));
         let remaining: i64 =
             latest_block_height as i64 - current_height as i64 - config.block_page_size as i64;
         if remaining <= 0 {
@@ -345,13 +542,248 @@ pub async fn block_synchronizer(
         } else {
             current_height += config.block_page_size;
         }
-        outstanding_requests += 1;
-        if outstanding_requests == max_requests {
-            join_all(block_transaction_futures).await;
-            outstanding_requests = 0;
-            block_transaction_futures = vec![];
+    }
+    sync_ranges(
+        &clients,
+        registry,
+        config,
+        msg_set,
+        ranges,
+        &mut cursor,
+        shutdown,
+    )
+    .await?;
+    info!(
+        "historical sync done: {} requests throttled by RPC nodes, {} delayed by rate limiting",
+        clients.throttled_requests(),
+        clients.delayed_requests()
+    );
+    Ok(latest_block_height)
+}
+
+// Indexes the blocks in `range` using `clients`, returning the ranges that
+// failed.
+async fn sync_range(
+    clients: &ClientPool,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    range: HeightRange,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<HeightRange>> {
+    info!("synchronizing blocks {}", range);
+    // The historical cursor only moves through `range` if it is contiguous
+    // with what was indexed before, so ranges that failed stay behind it.
+    let mut cursor = SyncCursor::starting_at(initial_sync_height(registry, config)?);
+    let ranges = range.split(config.block_page_size);
+    sync_ranges(
+        clients,
+        registry,
+        config,
+        msg_set,
+        ranges,
+        &mut cursor,
+        shutdown,
+    )
+    .await
+}
+
+/// Indexes the blocks in `range`, such as the gap between a finished
+/// historical sync and a live subscription. Returns the ranges that failed.
+pub async fn sync_block_range(
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    range: HeightRange,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<Vec<HeightRange>> {
+    let clients = connect_rpc_clients(config).await?;
+    sync_range(&clients, registry, config, msg_set, range, shutdown).await
+}
+
+/// Indexes the blocks from `start_height` up to the chain head, such as
+/// those produced while a live subscription was disconnected. Returns the
+/// chain head and the ranges that failed.
+pub async fn sync_to_chain_head(
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    start_height: u64,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<(u64, Vec<HeightRange>)> {
+    let clients = connect_rpc_clients(config).await?;
+    let chain_head = clients.latest_block_height().await?;
+    TARGET_HEIGHT.set(chain_head as i64);
+    if chain_head < start_height {
+        return Ok((chain_head, vec![]));
+    }
+    let range = HeightRange::new(start_height, chain_head);
+    let failed = sync_range(&clients, registry, config, msg_set, range, shutdown).await?;
+    Ok((chain_head, failed))
+}
+
+/// Outcome of a `backfill_synchronizer` run.
+#[derive(Debug)]
+pub struct BackfillReport {
+    pub first_height: u64,
+    pub last_height: u64,
+    /// Ranges that had not been indexed when the backfill started.
+    pub missing: Vec<HeightRange>,
+    /// Missing ranges that are still not indexed after the backfill.
+    pub unfilled: Vec<HeightRange>,
+}
+
+impl fmt::Display for BackfillReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing_blocks: u64 = self.missing.iter().map(|range| range.len()).sum();
+        let unfilled_blocks: u64 = self.unfilled.iter().map(|range| range.len()).sum();
+        writeln!(
+            f,
+            "Backfill of blocks {}-{}: {} missing ranges ({} blocks), {} still unfilled ({} blocks)",
+            self.first_height,
+            self.last_height,
+            self.missing.len(),
+            missing_blocks,
+            self.unfilled.len(),
+            unfilled_blocks
+        )?;
+        for range in &self.unfilled {
+            writeln!(f, "  unfilled: {}", range)?;
         }
+        Ok(())
     }
-    join_all(block_transaction_futures).await;
-    Ok(())
+}
+
+/// Finds the block ranges between the configured initial and final blocks
+/// that were never completely indexed and re-fetches only those.
+pub async fn backfill_synchronizer(
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<BackfillReport> {
+    let db = registry
+        .db
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Backfill requires a database to find missing blocks"))?;
+    let clients = connect_rpc_clients(config).await?;
+    let first_height = config.tendermint_initial_block;
+    let last_height = final_sync_height(&clients, config).await?;
+    TARGET_HEIGHT.set(last_height as i64);
+    let indexed_ranges = get_indexed_ranges(db, first_height, last_height)?;
+    let missing = missing_ranges(first_height, last_height, &indexed_ranges);
+    info!(
+        "backfilling {} missing ranges between blocks {} and {}",
+        missing.len(),
+        first_height,
+        last_height
+    );
+
+    let mut cursor = SyncCursor::starting_at(first_height);
+    let ranges: Vec<HeightRange> = missing
+        .iter()
+        .flat_map(|range| range.split(config.block_page_size))
+        .collect();
+    let unfilled = sync_ranges(
+        &clients,
+        registry,
+        config,
+        msg_set,
+        ranges,
+        &mut cursor,
+        shutdown,
+    )
+    .await?;
+
+    let report = BackfillReport {
+        first_height,
+        last_height,
+        missing,
+        unfilled: merge_ranges(&unfilled),
+    };
+    info!("{}", report);
+    info!(
+        "{} requests throttled by RPC nodes, {} delayed by rate limiting",
+        clients.throttled_requests(),
+        clients.delayed_requests()
+    );
+    Ok(report)
+}
+
+#[test]
+fn test_order_transactions() {
+    use tendermint::abci::transaction::Hash;
+    use tendermint::abci::Transaction;
+    use tendermint::block::Height;
+    let tx = |height: u64, index: u32| Response {
+        hash: Hash::new([index as u8; 32]),
+        height: Height::try_from(height).unwrap(),
+        index,
+        tx_result: Default::default(),
+        tx: Transaction::from(vec![]),
+        proof: None,
+    };
+    let txs = vec![
+        tx(12, 0),
+        tx(10, 1),
+        tx(10, 0),
+        tx(11, 0),
+        tx(10, 1),
+        tx(9, 3),
+    ];
+    let ordered: Vec<(u64, u32)> = order_transactions(txs, Some((9, 3)))
+        .iter()
+        .map(|tx| (tx.height.value(), tx.index))
+        .collect();
+    assert_eq!(vec![(10, 0), (10, 1), (11, 0), (12, 0)], ordered);
+}
+
+#[tokio::test]
+async fn test_empty_range_is_indexed() {
+    let config = IndexerConfig {
+        max_empty_block_retries: 1,
+        requeue_sleep: 1,
+        ..IndexerConfig::with_defaults()
+    };
+    let empty = || TxSearchResponse {
+        txs: vec![],
+        total_count: 0,
+    };
+    let queries_mutex = Mutex::from(QueryStream::new());
+    let fetched = Mutex::from(vec![]);
+    let mut tx_request = Box::new(TxSearchRequest::from_query_and_page(
+        Query::gte("tx.height", 1).and_lt("tx.height", 11),
+        1,
+    ));
+    tx_request.reque_count = 0;
+
+    // The first empty page is looked at again.
+    handle_transaction_response(
+        Ok(empty()),
+        tx_request,
+        1,
+        10,
+        &config,
+        &queries_mutex,
+        &fetched,
+    )
+    .await
+    .unwrap();
+    let tx_request = queries_mutex.lock().await.next().await.unwrap();
+    assert_eq!(1, tx_request.reque_count);
+
+    // Once out of retries the range is done, with nothing in it.
+    handle_transaction_response(
+        Ok(empty()),
+        tx_request,
+        1,
+        10,
+        &config,
+        &queries_mutex,
+        &fetched,
+    )
+    .await
+    .unwrap();
+    assert!(queries_mutex.lock().await.is_empty());
+    assert!(fetched.lock().await.is_empty());
 }
@@ -10,3 +10,4 @@ pub mod parse;
 pub mod schema_indexer;
 mod test_schema_indexer;
 pub mod tx;
+pub mod tx_events;
@@ -1,12 +1,12 @@
-use super::event_map::EventMap;
 use super::indexer_registry::IndexerRegistry;
+use super::tx_events::TxEvents;
 
 pub trait IndexMessage {
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()>;
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()>;
 }
 
 impl IndexMessage for () {
-    fn index_message(&self, _registry: &IndexerRegistry, _events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, _registry: &IndexerRegistry, _events: &TxEvents) -> anyhow::Result<()> {
         Ok(())
     }
 }
@@ -1,9 +1,16 @@
+pub mod msg_authz;
 pub mod msg_cw20_execute;
 pub mod msg_cw3_multisig_inst_msg;
 pub mod msg_cw3dao_instantiate_contract;
 pub mod msg_cw3multisig_execute_contract;
 pub mod msg_dao_execute;
 pub mod msg_execute_contract;
+pub mod msg_gov;
+pub mod msg_ibc;
 pub mod msg_instantiate_contract;
+pub mod msg_migrate_contract;
 pub mod msg_send;
 pub mod msg_stake_cw20_execute;
+pub mod msg_staking;
+pub mod msg_store_code;
+pub mod msg_update_admin;
@@ -0,0 +1,158 @@
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
+use anyhow::anyhow;
+use cosmos_sdk_proto::cosmos::authz::v1beta1::{GenericAuthorization, MsgGrant, MsgRevoke};
+use cosmos_sdk_proto::cosmos::bank::v1beta1::SendAuthorization;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::StakeAuthorization;
+use diesel::prelude::*;
+use log::debug;
+use prost::Message;
+use prost_types::Any;
+use tendermint::Time;
+
+// The message a grant lets its grantee execute. This is also how a
+// MsgRevoke names the grant it ends.
+fn granted_msg_type_url(authorization: &Any) -> anyhow::Result<String> {
+    let msg_type_url = match authorization.type_url.as_str() {
+        "/cosmos.authz.v1beta1.GenericAuthorization" => {
+            GenericAuthorization::decode(authorization.value.as_slice())?.msg
+        }
+        "/cosmos.bank.v1beta1.SendAuthorization" => {
+            // Checks it decodes; it only ever grants MsgSend.
+            SendAuthorization::decode(authorization.value.as_slice())?;
+            "/cosmos.bank.v1beta1.MsgSend".to_string()
+        }
+        "/cosmos.staking.v1beta1.StakeAuthorization" => {
+            let stake_authorization = StakeAuthorization::decode(authorization.value.as_slice())?;
+            match stake_authorization.authorization_type {
+                1 => "/cosmos.staking.v1beta1.MsgDelegate",
+                2 => "/cosmos.staking.v1beta1.MsgUndelegate",
+                3 => "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+                other => return Err(anyhow!("unknown stake authorization type {}", other)),
+            }
+            .to_string()
+        }
+        other => other.to_string(),
+    };
+    Ok(msg_type_url)
+}
+
+impl IndexMessage for MsgGrant {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        // A rejected grant never let anyone do anything.
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let grant = self
+            .grant
+            .as_ref()
+            .ok_or_else(|| anyhow!("MsgGrant without a grant"))?;
+        let authorization = grant
+            .authorization
+            .as_ref()
+            .ok_or_else(|| anyhow!("MsgGrant without an authorization"))?;
+        let expiration = match &grant.expiration {
+            Some(expiration) => Some(
+                Time::from_unix_timestamp(expiration.seconds, expiration.nanos as u32)?
+                    .to_rfc3339(),
+            ),
+            None => None,
+        };
+        let height = tx_height(events)?;
+        let message_key = MessageKey::from_events(events, 0);
+        debug!(
+            "{} granted {} to {}",
+            self.granter, authorization.type_url, self.grantee
+        );
+
+        use crate::db::schema::authz_grant::dsl;
+        diesel::insert_into(dsl::authz_grant)
+            .values((
+                dsl::granter.eq(&self.granter),
+                dsl::grantee.eq(&self.grantee),
+                dsl::msg_type_url.eq(granted_msg_type_url(authorization)?),
+                dsl::authorization_type_url.eq(&authorization.type_url),
+                dsl::expiration.eq(expiration),
+                dsl::height.eq(height),
+                dsl::tx_hash.eq(&message_key.tx_hash),
+                dsl::msg_index.eq(message_key.msg_index),
+            ))
+            .on_conflict_do_nothing()
+            .execute(db)?;
+        Ok(())
+    }
+}
+
+impl IndexMessage for MsgRevoke {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let height = tx_height(events)?;
+        let message_key = MessageKey::from_events(events, 0);
+        debug!(
+            "{} revoked {} from {}",
+            self.granter, self.msg_type_url, self.grantee
+        );
+
+        use crate::db::schema::authz_grant::dsl;
+        diesel::update(
+            dsl::authz_grant
+                .filter(dsl::granter.eq(&self.granter))
+                .filter(dsl::grantee.eq(&self.grantee))
+                .filter(dsl::msg_type_url.eq(&self.msg_type_url))
+                .filter(dsl::revoked_height.is_null()),
+        )
+        .set((
+            dsl::revoked_height.eq(height),
+            dsl::revoked_tx_hash.eq(&message_key.tx_hash),
+        ))
+        .execute(db)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_granted_msg_type_url() {
+    let generic = Any {
+        type_url: "/cosmos.authz.v1beta1.GenericAuthorization".to_string(),
+        value: GenericAuthorization {
+            msg: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+        }
+        .encode_to_vec(),
+    };
+    assert_eq!(
+        "/cosmwasm.wasm.v1.MsgExecuteContract",
+        granted_msg_type_url(&generic).unwrap()
+    );
+    let stake = Any {
+        type_url: "/cosmos.staking.v1beta1.StakeAuthorization".to_string(),
+        value: StakeAuthorization {
+            authorization_type: 2,
+            ..Default::default()
+        }
+        .encode_to_vec(),
+    };
+    assert_eq!(
+        "/cosmos.staking.v1beta1.MsgUndelegate",
+        granted_msg_type_url(&stake).unwrap()
+    );
+    let send = Any {
+        type_url: "/cosmos.bank.v1beta1.SendAuthorization".to_string(),
+        value: SendAuthorization::default().encode_to_vec(),
+    };
+    assert_eq!(
+        "/cosmos.bank.v1beta1.MsgSend",
+        granted_msg_type_url(&send).unwrap()
+    );
+}
@@ -1,7 +1,7 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
-use crate::util::debug::{dump_events, events_string};
+use crate::indexing::tx_events::TxEvents;
+use crate::util::message_key::{tx_height, MessageKey};
 use crate::util::update_balance::update_balance;
 use anyhow::anyhow;
 use bigdecimal::BigDecimal;
@@ -11,80 +11,60 @@ use log::error;
 use std::str::FromStr;
 
 impl IndexMessage for Cw20ExecuteMsg {
-    fn index_message(
-        &self,
-        registry: &IndexerRegistry,
-        event_map: &EventMap,
-    ) -> anyhow::Result<()> {
-        dump_events(event_map);
-        if let Some(wasm_actions) = event_map.get("wasm.action") {
-            if !wasm_actions.is_empty() && &wasm_actions[0] == "send" {
-                let tx_height = BigDecimal::from_str(
-                    &({
-                        let this = event_map.get("tx.height");
-                        if let Some(val) = this {
-                            val
-                        } else {
-                            error!("{}", events_string(event_map));
-                            panic!("called `Option::unwrap()` on a `None` value")
-                        }
-                    }[0]),
-                )?;
-                let contract_addresses = event_map
-                    .get("wasm._contract_address")
-                    .ok_or_else(|| anyhow!("no wasm._contract_address"))?;
-                let gov_token_address = &contract_addresses[0];
-                let to_addresses = event_map
-                    .get("wasm.to")
-                    .ok_or_else(|| anyhow!("no wasm.to"))?;
-                let staking_contract_addr = to_addresses[0].clone();
-                let amounts = &event_map
-                    .get("wasm.amount")
-                    .ok_or_else(|| anyhow!("no wasm.amount"))?;
-                let senders = event_map
-                    .get("wasm.from")
-                    .ok_or_else(|| anyhow!("no wasm.from"))?;
-                let sender_addr = &senders[0];
-                let mut send_amount: &str = &amounts[0];
-
-                let receiving_contract_action: &str = if wasm_actions.len() > 1 {
-                    &wasm_actions[1]
-                } else {
-                    ""
-                };
-
-                let action_amount: &str = if amounts.len() > 1 {
-                    &amounts[1]
-                } else {
-                    error!("Expected more than one amount, but got: {:?}", amounts);
-                    "0"
-                };
-
-                if receiving_contract_action == "stake" {
-                    send_amount = action_amount;
-                }
-                let mut amount: Uint128 = Uint128::new(0);
-                match Uint128::from_str(send_amount) {
-                    Ok(parsed_amount) => {
-                        amount = parsed_amount;
-                    }
-                    Err(e) => {
-                        // Try to parse as a decimal
-                        let decimal_amount = BigDecimal::from_str(send_amount)?;
-                        error!("Parsed as {:?} due to error {:?}", decimal_amount, e)
-                    }
-                }
-                if registry.db.is_some() {
-                    update_balance(
-                        registry,
-                        Some(&tx_height),
-                        gov_token_address,
-                        sender_addr,
-                        &staking_contract_addr,
-                        u128::from(amount),
-                    )?;
-                }
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let (contract, sent) = match self {
+            Cw20ExecuteMsg::Send {
+                contract, amount, ..
+            } => (contract, amount.to_string()),
+            _ => return Ok(()),
+        };
+        // The send needn't be the first thing the message did, and other
+        // contracts can emit send actions of their own.
+        let send = match events.of_type("wasm").find(|event| {
+            event.attribute("action") == Some("send")
+                && event.attribute("to") == Some(contract.as_str())
+                && event.attribute("amount") == Some(sent.as_str())
+        }) {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        let tx_height = BigDecimal::from(tx_height(events)?);
+        let gov_token_address = send
+            .contract_address
+            .as_deref()
+            .ok_or_else(|| anyhow!("no _contract_address in cw20 send event"))?;
+        let staking_contract_addr = send.require("to")?;
+        let sender_addr = send.require("from")?;
+        // Tokens sent to a staking contract are staked, and the amount
+        // staked is what its stake event reports.
+        let send_amount = match events
+            .wasm_events_from(staking_contract_addr)
+            .find(|event| event.attribute("action") == Some("stake"))
+        {
+            Some(stake) => stake.require("amount")?,
+            None => send.require("amount")?,
+        };
+        let mut amount: Uint128 = Uint128::new(0);
+        match Uint128::from_str(send_amount) {
+            Ok(parsed_amount) => {
+                amount = parsed_amount;
             }
+            Err(e) => {
+                // Try to parse as a decimal
+                let decimal_amount = BigDecimal::from_str(send_amount)?;
+                error!("Parsed as {:?} due to error {:?}", decimal_amount, e)
+            }
+        }
+        if registry.db.is_some() {
+            update_balance(
+                registry,
+                Some(&tx_height),
+                gov_token_address,
+                sender_addr,
+                staking_contract_addr,
+                u128::from(amount),
+                &MessageKey::from_events(events, 0),
+            )?;
         }
         Ok(())
     }
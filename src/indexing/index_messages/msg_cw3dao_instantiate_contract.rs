@@ -1,14 +1,15 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
 use crate::util::contract_util::get_contract_addresses;
 use crate::util::dao::{get_tx_height_from_events, insert_dao, insert_dao_25};
+use crate::util::message_key::MessageKey;
 
 use cw3_dao::msg::InstantiateMsg as Cw3DaoInstantiateMsg;
 use cw3_dao_2_5::msg::InstantiateMsg as Cw3DaoInstantiateMsg25;
 
 impl IndexMessage for Cw3DaoInstantiateMsg {
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         let contract_addresses = get_contract_addresses(events);
         let tx_height = get_tx_height_from_events(events);
         insert_dao(
@@ -19,12 +20,13 @@ impl IndexMessage for Cw3DaoInstantiateMsg {
             self.image_url.as_ref(),
             &contract_addresses,
             Some(&tx_height),
+            &MessageKey::from_events(events, 0),
         )
     }
 }
 
 impl IndexMessage for Cw3DaoInstantiateMsg25 {
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         let contract_addresses = get_contract_addresses(events);
         let tx_height = get_tx_height_from_events(events);
         insert_dao_25(
@@ -35,6 +37,7 @@ impl IndexMessage for Cw3DaoInstantiateMsg25 {
             self.image_url.as_ref(),
             &contract_addresses,
             Some(&tx_height),
+            &MessageKey::from_events(events, 0),
         )
     }
 }
@@ -1,6 +1,6 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
 use crate::util::debug::dump_events;
 pub use cw3_multisig::msg::ExecuteMsg;
 use log::warn;
@@ -9,7 +9,7 @@ impl IndexMessage for ExecuteMsg {
     fn index_message(
         &self,
         _registry: &IndexerRegistry,
-        event_map: &EventMap,
+        event_map: &TxEvents,
     ) -> anyhow::Result<()> {
         dump_events(event_map);
         warn!("index_message not implemented for cw3_multisig ExecuteMsg");
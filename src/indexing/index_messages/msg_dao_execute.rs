@@ -1,6 +1,6 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
 use crate::util::debug::{dump_events, dump_execute_contract};
 use crate::util::update_balance::update_balance_from_events;
 use anyhow::anyhow;
@@ -10,7 +10,7 @@ impl IndexMessage for ExecuteMsg {
     fn index_message(
         &self,
         registry: &IndexerRegistry,
-        event_map: &EventMap,
+        event_map: &TxEvents,
     ) -> anyhow::Result<()> {
         if registry.db.is_none() {
             return Err(anyhow!("No db connection available"));
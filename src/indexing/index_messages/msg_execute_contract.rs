@@ -1,14 +1,26 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::bank_transfer::{insert_bank_transfers, transfers_from_events};
+use crate::util::message_key::tx_failed;
 use cosmrs::proto::cosmwasm::wasm::v1::MsgExecuteContract;
 pub use cw20::Cw20ExecuteMsg;
 use serde_json::Value;
 
 impl IndexMessage for MsgExecuteContract {
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         let msg_str = String::from_utf8(self.msg.clone())?;
         let msg_val: Value = serde_json::from_str(&msg_str)?;
-        registry.index_message_and_events(events, &msg_val, &msg_str)
+        registry.index_message_and_events(events, &msg_val, &msg_str)?;
+        if let Some(db) = &registry.db {
+            // Contracts, DAO treasuries among them, move native tokens with
+            // bank messages of their own that only show up in the events,
+            // which have to be this message's alone not to record another
+            // message's transfers as well.
+            if !tx_failed(events) && !events.shared() {
+                insert_bank_transfers(db, events, &transfers_from_events(events)?)?;
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,266 @@
+use crate::db::models::{NewGovDeposit, NewGovVote};
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use cosmos_sdk_proto::cosmos::gov::v1beta1::{
+    MsgDeposit, MsgSubmitProposal, MsgVote, MsgVoteWeighted, TextProposal, WeightedVoteOption,
+};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use log::debug;
+use num_bigint::BigInt;
+use prost::Message;
+use std::str::FromStr;
+
+// Vote weights are sdk.Decs, which are encoded as integers scaled up by
+// 10^18.
+const DEC_PRECISION: i64 = 18;
+
+fn parse_weight(weight: &str) -> anyhow::Result<BigDecimal> {
+    if weight.contains('.') {
+        return Ok(BigDecimal::from_str(weight)?);
+    }
+    Ok(BigDecimal::new(BigInt::from_str(weight)?, DEC_PRECISION))
+}
+
+fn vote_option_name(option: i32) -> anyhow::Result<&'static str> {
+    match option {
+        1 => Ok("yes"),
+        2 => Ok("abstain"),
+        3 => Ok("no"),
+        4 => Ok("no_with_veto"),
+        other => Err(anyhow!("unknown vote option {}", other)),
+    }
+}
+
+// Whether proposal `proposal_id` entered its voting period: a deposit that
+// was enough for that marks its proposal_deposit event, and an initial
+// deposit that was enough marks the submit_proposal event instead.
+fn voting_started(events: &TxEvents, proposal_id: u64) -> bool {
+    let proposal_id = proposal_id.to_string();
+    ["proposal_deposit", "submit_proposal"]
+        .iter()
+        .flat_map(|type_str| events.of_type(type_str))
+        .any(|event| {
+            event
+                .attributes("voting_period_start")
+                .any(|id| id == proposal_id)
+        })
+}
+
+// Records the deposit, and the proposal entering its voting period if the
+// deposit was enough for that.
+fn insert_deposit(
+    db: &PgConnection,
+    events: &TxEvents,
+    proposal_id: u64,
+    depositor: &str,
+    coins: &[Coin],
+) -> anyhow::Result<()> {
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    let mut deposits = vec![];
+    for (event_index, coin) in coins.iter().enumerate() {
+        deposits.push(NewGovDeposit {
+            proposal_id: proposal_id as i64,
+            depositor,
+            denom: &coin.denom,
+            amount: BigDecimal::from_str(&coin.amount)?,
+            height,
+            tx_hash: message_key.tx_hash.clone(),
+            msg_index: message_key.msg_index,
+            event_index: event_index as i32,
+        });
+    }
+    diesel::insert_into(crate::db::schema::gov_deposit::table)
+        .values(&deposits)
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    if voting_started(events, proposal_id) {
+        use crate::db::schema::gov_proposal::dsl;
+        diesel::update(dsl::gov_proposal.filter(dsl::proposal_id.eq(proposal_id as i64)))
+            .set(dsl::voting_start_height.eq(height))
+            .execute(db)?;
+    }
+    Ok(())
+}
+
+fn insert_vote(
+    db: &PgConnection,
+    events: &TxEvents,
+    proposal_id: u64,
+    voter: &str,
+    options: &[WeightedVoteOption],
+) -> anyhow::Result<()> {
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    let mut votes = vec![];
+    for (event_index, option) in options.iter().enumerate() {
+        votes.push(NewGovVote {
+            proposal_id: proposal_id as i64,
+            voter,
+            option: vote_option_name(option.option)?,
+            weight: parse_weight(&option.weight)?,
+            height,
+            tx_hash: message_key.tx_hash.clone(),
+            msg_index: message_key.msg_index,
+            event_index: event_index as i32,
+        });
+    }
+    diesel::insert_into(crate::db::schema::gov_vote::table)
+        .values(&votes)
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    Ok(())
+}
+
+impl IndexMessage for MsgSubmitProposal {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let proposal_id: u64 = events
+            .first_attribute("submit_proposal", "proposal_id")?
+            .parse()?;
+        let content = self
+            .content
+            .as_ref()
+            .ok_or_else(|| anyhow!("proposal {} has no content", proposal_id))?;
+        // Every v1beta1 proposal type starts with a title and description,
+        // so reading any of them as a text proposal gets those.
+        let text = TextProposal::decode(content.value.as_slice())?;
+        let height = tx_height(events)?;
+        debug!("{} submitted proposal {}", self.proposer, proposal_id);
+
+        use crate::db::schema::gov_proposal::dsl;
+        diesel::insert_into(dsl::gov_proposal)
+            .values((
+                dsl::proposal_id.eq(proposal_id as i64),
+                dsl::proposer.eq(&self.proposer),
+                dsl::content_type_url.eq(&content.type_url),
+                dsl::title.eq(&text.title),
+                dsl::description.eq(&text.description),
+                dsl::height.eq(height),
+                dsl::tx_hash.eq(MessageKey::from_events(events, 0).tx_hash),
+            ))
+            .on_conflict_do_nothing()
+            .execute(db)?;
+        insert_deposit(
+            db,
+            events,
+            proposal_id,
+            &self.proposer,
+            &self.initial_deposit,
+        )
+    }
+}
+
+impl IndexMessage for MsgDeposit {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        insert_deposit(db, events, self.proposal_id, &self.depositor, &self.amount)
+    }
+}
+
+impl IndexMessage for MsgVote {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let option = WeightedVoteOption {
+            option: self.option,
+            weight: "1.0".to_string(),
+        };
+        insert_vote(db, events, self.proposal_id, &self.voter, &[option])
+    }
+}
+
+impl IndexMessage for MsgVoteWeighted {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        insert_vote(db, events, self.proposal_id, &self.voter, &self.options)
+    }
+}
+
+#[test]
+fn test_vote_options() {
+    assert_eq!(
+        BigDecimal::from_str("0.25").unwrap(),
+        parse_weight("250000000000000000").unwrap()
+    );
+    assert_eq!(
+        BigDecimal::from(1),
+        parse_weight("1000000000000000000").unwrap()
+    );
+    assert_eq!(
+        BigDecimal::from_str("0.5").unwrap(),
+        parse_weight("0.500000000000000000").unwrap()
+    );
+    assert!(parse_weight("half").is_err());
+    assert_eq!("no_with_veto", vote_option_name(4).unwrap());
+    assert!(vote_option_name(0).is_err());
+}
+
+#[test]
+fn test_voting_started() {
+    use crate::indexing::tx_events::TxEvent;
+    let event = |type_str: &str, attributes: &[(&str, &str)]| {
+        TxEvent::new(
+            type_str,
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    };
+    // An initial deposit that was enough.
+    let submitted = TxEvents::new(vec![event(
+        "submit_proposal",
+        &[
+            ("proposal_id", "7"),
+            ("proposal_type", "Text"),
+            ("voting_period_start", "7"),
+        ],
+    )]);
+    assert!(voting_started(&submitted, 7));
+    // A later deposit that was enough.
+    let deposited = TxEvents::new(vec![event(
+        "proposal_deposit",
+        &[
+            ("amount", "10ujuno"),
+            ("proposal_id", "7"),
+            ("voting_period_start", "7"),
+        ],
+    )]);
+    assert!(voting_started(&deposited, 7));
+    assert!(!voting_started(&deposited, 8));
+    let not_enough = TxEvents::new(vec![event(
+        "submit_proposal",
+        &[("proposal_id", "7"), ("proposal_type", "Text")],
+    )]);
+    assert!(!voting_started(&not_enough, 7));
+}
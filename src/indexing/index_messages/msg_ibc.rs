@@ -0,0 +1,308 @@
+use crate::db::models::NewIbcTransfer;
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::{TxEvent, TxEvents};
+use crate::util::ibc::{
+    acknowledgement_error, ibc_denom, received_denom_trace, split_denom_trace,
+    FungibleTokenPacketData, ACKNOWLEDGED, FAILED, INCOMING, OUTGOING, PENDING, RECEIVED,
+    RECEIVE_FAILED, TIMED_OUT,
+};
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use cosmos_sdk_proto::ibc::applications::transfer::v1::MsgTransfer;
+use cosmos_sdk_proto::ibc::core::channel::v1::{
+    MsgAcknowledgement, MsgRecvPacket, MsgTimeout, Packet,
+};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use log::debug;
+use std::str::FromStr;
+
+// The transfer a packet carries, or None for packets of other applications,
+// like interchain accounts.
+fn transfer_data(
+    packet: &Option<Packet>,
+) -> anyhow::Result<Option<(&Packet, FungibleTokenPacketData)>> {
+    let packet = packet.as_ref().ok_or_else(|| anyhow!("no packet"))?;
+    Ok(FungibleTokenPacketData::from_bytes(&packet.data)
+        .ok()
+        .map(|data| (packet, data)))
+}
+
+// Settles an outgoing transfer. Only pending transfers are settled, so a
+// relayer delivering an acknowledgement or timeout again changes nothing.
+fn resolve_outgoing(
+    db: &PgConnection,
+    events: &TxEvents,
+    packet: &Packet,
+    status: &str,
+    error: Option<String>,
+) -> anyhow::Result<()> {
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    debug!(
+        "transfer {}/{} #{} {}",
+        packet.source_port, packet.source_channel, packet.sequence, status
+    );
+    use crate::db::schema::ibc_transfer::dsl;
+    diesel::update(
+        dsl::ibc_transfer
+            .filter(dsl::direction.eq(OUTGOING))
+            .filter(dsl::source_port.eq(&packet.source_port))
+            .filter(dsl::source_channel.eq(&packet.source_channel))
+            .filter(dsl::sequence.eq(packet.sequence as i64))
+            .filter(dsl::status.eq(PENDING)),
+    )
+    .set((
+        dsl::status.eq(status),
+        dsl::error.eq(error),
+        dsl::resolved_height.eq(height),
+        dsl::resolved_tx_hash.eq(message_key.tx_hash),
+    ))
+    .execute(db)?;
+    Ok(())
+}
+
+// The send_packet event of a transfer, told apart from the packets other
+// messages of the transaction sent by its channel and its data.
+fn send_packet(
+    msg: &MsgTransfer,
+    token: &Coin,
+    events: &TxEvents,
+) -> anyhow::Result<(TxEvent, FungibleTokenPacketData)> {
+    let mut packets = events
+        .records("send_packet")
+        .into_iter()
+        .filter_map(|packet| {
+            if packet.attribute("packet_src_port") != Some(msg.source_port.as_str())
+                || packet.attribute("packet_src_channel") != Some(msg.source_channel.as_str())
+            {
+                return None;
+            }
+            let data =
+                FungibleTokenPacketData::from_bytes(packet.attribute("packet_data")?.as_bytes())
+                    .ok()?;
+            let sent = data.sender == msg.sender
+                && data.receiver == msg.receiver
+                && data.amount == token.amount
+                && ibc_denom(&data.denom) == token.denom;
+            sent.then(|| (packet, data))
+        });
+    match (packets.next(), packets.next()) {
+        (Some(packet), None) => Ok(packet),
+        (Some(_), Some(_)) => Err(anyhow!(
+            "several send_packet events match the transfer on {}/{}",
+            msg.source_port,
+            msg.source_channel
+        )),
+        _ => Err(anyhow!(
+            "no send_packet event matches the transfer on {}/{}",
+            msg.source_port,
+            msg.source_channel
+        )),
+    }
+}
+
+impl IndexMessage for MsgTransfer {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| anyhow!("MsgTransfer without a token"))?;
+        let (packet, data) = send_packet(self, token, events)?;
+        // The packet carries the token's full trace, where the message only
+        // has its denom on this chain.
+        let denom_trace = data.denom;
+        let (_, base_denom) = split_denom_trace(&denom_trace);
+        let transfer = NewIbcTransfer {
+            direction: OUTGOING,
+            source_port: &self.source_port,
+            source_channel: &self.source_channel,
+            sequence: packet.require("packet_sequence")?.parse()?,
+            destination_port: packet.require("packet_dst_port")?,
+            destination_channel: packet.require("packet_dst_channel")?,
+            sender: &self.sender,
+            receiver: &self.receiver,
+            denom: token.denom.clone(),
+            denom_trace: &denom_trace,
+            base_denom: &base_denom,
+            amount: BigDecimal::from_str(&token.amount)?,
+            status: PENDING,
+            error: None,
+            height: tx_height(events)?,
+            tx_hash: MessageKey::from_events(events, 0).tx_hash,
+        };
+        diesel::insert_into(crate::db::schema::ibc_transfer::table)
+            .values(&transfer)
+            .on_conflict_do_nothing()
+            .execute(db)?;
+        Ok(())
+    }
+}
+
+impl IndexMessage for MsgRecvPacket {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let (packet, data) = match transfer_data(&self.packet)? {
+            Some(transfer) => transfer,
+            None => return Ok(()),
+        };
+        // A packet another relayer delivered first is received again
+        // without any effect.
+        if events.first_event("recv_packet").is_err() {
+            return Ok(());
+        }
+        let error = match events.first_attribute("write_acknowledgement", "packet_ack") {
+            Ok(packet_ack) => acknowledgement_error(packet_ack.as_bytes())?,
+            Err(_) => None,
+        };
+        let denom_trace = received_denom_trace(
+            &packet.source_port,
+            &packet.source_channel,
+            &packet.destination_port,
+            &packet.destination_channel,
+            &data.denom,
+        );
+        let (_, base_denom) = split_denom_trace(&denom_trace);
+        let transfer = NewIbcTransfer {
+            direction: INCOMING,
+            source_port: &packet.source_port,
+            source_channel: &packet.source_channel,
+            sequence: packet.sequence as i64,
+            destination_port: &packet.destination_port,
+            destination_channel: &packet.destination_channel,
+            sender: &data.sender,
+            receiver: &data.receiver,
+            denom: ibc_denom(&denom_trace),
+            denom_trace: &denom_trace,
+            base_denom: &base_denom,
+            amount: BigDecimal::from_str(&data.amount)?,
+            status: if error.is_some() {
+                RECEIVE_FAILED
+            } else {
+                RECEIVED
+            },
+            error,
+            height: tx_height(events)?,
+            tx_hash: MessageKey::from_events(events, 0).tx_hash,
+        };
+        diesel::insert_into(crate::db::schema::ibc_transfer::table)
+            .values(&transfer)
+            .on_conflict_do_nothing()
+            .execute(db)?;
+        Ok(())
+    }
+}
+
+impl IndexMessage for MsgAcknowledgement {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let packet = match transfer_data(&self.packet)? {
+            Some((packet, _)) => packet,
+            None => return Ok(()),
+        };
+        let error = acknowledgement_error(&self.acknowledgement)?;
+        let status = if error.is_some() {
+            FAILED
+        } else {
+            ACKNOWLEDGED
+        };
+        resolve_outgoing(db, events, packet, status, error)
+    }
+}
+
+impl IndexMessage for MsgTimeout {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let packet = match transfer_data(&self.packet)? {
+            Some((packet, _)) => packet,
+            None => return Ok(()),
+        };
+        resolve_outgoing(db, events, packet, TIMED_OUT, None)
+    }
+}
+
+#[test]
+fn test_send_packet() {
+    let packet = |channel: &str, sequence: &str, amount: &str| {
+        let data = format!(
+            r#"{{"amount":"{}","denom":"ujuno","receiver":"osmo1receiver","sender":"juno1sender"}}"#,
+            amount
+        );
+        [
+            ("packet_data", data),
+            ("packet_sequence", sequence.to_string()),
+            ("packet_src_port", "transfer".to_string()),
+            ("packet_src_channel", channel.to_string()),
+        ]
+        .map(|(key, value)| (key.to_string(), value))
+    };
+    // Merged from a message log, as without ordered events every transfer
+    // of the transaction sees all of its packets.
+    let events = TxEvents::new(vec![TxEvent::new(
+        "send_packet",
+        [
+            packet("channel-0", "1", "10"),
+            packet("channel-0", "2", "20"),
+            packet("channel-1", "3", "20"),
+        ]
+        .concat(),
+    )]);
+    let token = Coin {
+        denom: "ujuno".to_string(),
+        amount: "20".to_string(),
+    };
+    let msg = MsgTransfer {
+        source_port: "transfer".to_string(),
+        source_channel: "channel-0".to_string(),
+        token: Some(token.clone()),
+        sender: "juno1sender".to_string(),
+        receiver: "osmo1receiver".to_string(),
+        ..Default::default()
+    };
+    let (packet_event, data) = send_packet(&msg, &token, &events).unwrap();
+    assert_eq!(Some("2"), packet_event.attribute("packet_sequence"));
+    assert_eq!("20", data.amount);
+
+    let other_channel = MsgTransfer {
+        source_channel: "channel-2".to_string(),
+        ..msg.clone()
+    };
+    assert!(send_packet(&other_channel, &token, &events).is_err());
+    let twice = TxEvents::new(vec![TxEvent::new(
+        "send_packet",
+        [
+            packet("channel-0", "1", "20"),
+            packet("channel-0", "2", "20"),
+        ]
+        .concat(),
+    )]);
+    assert!(send_packet(&msg, &token, &twice).is_err());
+}
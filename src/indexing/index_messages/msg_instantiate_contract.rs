@@ -1,8 +1,11 @@
-use crate::db::models::NewContract;
-use crate::indexing::event_map::EventMap;
+use crate::db::models::{NewContract, NewContractHistory};
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
-use crate::util::contract_util::{get_contract_addresses, insert_contract};
+use crate::indexing::tx_events::TxEvents;
+use crate::util::contract_util::{
+    get_contract_addresses, insert_contract, insert_contract_history,
+};
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
 use anyhow::anyhow;
 use bigdecimal::BigDecimal;
 use cosmrs::cosmwasm::MsgInstantiateContract;
@@ -10,17 +13,21 @@ use log::{debug, error};
 use std::str::FromStr;
 
 impl IndexMessage for MsgInstantiateContract {
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         let db = match &registry.db {
             Some(registry_db) => registry_db,
             _ => return Ok(()),
         };
 
         debug!("Indexing MsgInstantiateContract, events: {:?}", events);
-        let contract_model = create_new_contract(self, events)?;
-
-        if let Err(e) = insert_contract(db, &contract_model) {
-            error!("Error inserting contract {:?}\n{:?}", &contract_model, e);
+        // A failed instantiation created no contract, but indexers that
+        // opted in to failed transactions still see its message.
+        if !tx_failed(events) {
+            insert_instantiate_history(db, self, events)?;
+            let contract_model = create_new_contract(self, events)?;
+            if let Err(e) = insert_contract(db, &contract_model) {
+                error!("Error inserting contract {:?}\n{:?}", &contract_model, e);
+            }
         }
 
         let msg_str = String::from_utf8(self.msg.clone())?;
@@ -31,6 +38,38 @@ impl IndexMessage for MsgInstantiateContract {
     }
 }
 
+// Starts the contract's history with the code and admin it was
+// instantiated with.
+fn insert_instantiate_history(
+    db: &diesel::PgConnection,
+    msg_inst_contract: &MsgInstantiateContract,
+    events: &TxEvents,
+) -> anyhow::Result<()> {
+    let contract_address = match events.first_attribute("instantiate", "_contract_address") {
+        Ok(contract_address) => contract_address,
+        Err(_) => return Ok(()),
+    };
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    let admin = msg_inst_contract
+        .admin
+        .as_ref()
+        .map(|admin| admin.to_string());
+    insert_contract_history(
+        db,
+        &NewContractHistory {
+            contract_address,
+            operation: "instantiate",
+            code_id: Some(msg_inst_contract.code_id as i64),
+            admin: admin.as_deref(),
+            sender: msg_inst_contract.sender.as_ref(),
+            height,
+            tx_hash: message_key.tx_hash,
+            msg_index: message_key.msg_index,
+        },
+    )
+}
+
 fn create_new_contract<'a>(
     msg_inst_contract: &'a MsgInstantiateContract,
     events: &'a std::collections::BTreeMap<String, Vec<String>>,
@@ -0,0 +1,40 @@
+use crate::db::models::NewContractHistory;
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::contract_util::{insert_contract_history, update_contract_code_id};
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
+use cosmrs::proto::cosmwasm::wasm::v1::MsgMigrateContract;
+use log::debug;
+
+impl IndexMessage for MsgMigrateContract {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let height = tx_height(events)?;
+        let message_key = MessageKey::from_events(events, 0);
+        debug!(
+            "{} migrated {} to code {}",
+            self.sender, self.contract, self.code_id
+        );
+        insert_contract_history(
+            db,
+            &NewContractHistory {
+                contract_address: &self.contract,
+                operation: "migrate",
+                code_id: Some(self.code_id as i64),
+                admin: None,
+                sender: &self.sender,
+                height,
+                tx_hash: message_key.tx_hash,
+                msg_index: message_key.msg_index,
+            },
+        )?;
+        update_contract_code_id(db, &self.contract)
+    }
+}
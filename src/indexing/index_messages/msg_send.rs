@@ -1,12 +1,70 @@
-use crate::indexing::debug::index_message;
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
-use cosmrs::proto::cosmos::bank::v1beta1::MsgSend;
-pub use cw20::Cw20ExecuteMsg;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::bank_transfer::{insert_bank_transfers, transfers_from_events, BankTransfer};
+use crate::util::message_key::tx_failed;
+use bigdecimal::BigDecimal;
+use cosmrs::proto::cosmos::bank::v1beta1::{MsgMultiSend, MsgSend};
+use cosmrs::proto::cosmos::base::v1beta1::Coin;
+use std::str::FromStr;
+
+fn coin_transfers(
+    from_address: &str,
+    to_address: &str,
+    coins: &[Coin],
+) -> anyhow::Result<Vec<BankTransfer>> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(BankTransfer::new(
+                from_address,
+                to_address,
+                &coin.denom,
+                BigDecimal::from_str(&coin.amount)?,
+            ))
+        })
+        .collect()
+}
 
 impl IndexMessage for MsgSend {
-    fn index_message(&self, _registry: &IndexerRegistry, _events: &EventMap) -> anyhow::Result<()> {
-        index_message(&self.from_address, &self.to_address, &self.amount, None)
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        let transfers = coin_transfers(&self.from_address, &self.to_address, &self.amount)?;
+        insert_bank_transfers(db, events, &transfers)
+    }
+}
+
+impl IndexMessage for MsgMultiSend {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        if tx_failed(events) {
+            return Ok(());
+        }
+        // With a single input every output is paid by it. With several
+        // there's no saying which paid whom but what the events show.
+        let transfers = match self.inputs.as_slice() {
+            [input] => {
+                let mut transfers = vec![];
+                for output in self.outputs.iter() {
+                    transfers.extend(coin_transfers(
+                        &input.address,
+                        &output.address,
+                        &output.coins,
+                    )?);
+                }
+                transfers
+            }
+            _ => transfers_from_events(events)?,
+        };
+        insert_bank_transfers(db, events, &transfers)
     }
 }
@@ -1,12 +1,12 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
 use crate::util::debug::dump_events;
 use log::debug;
 use stake_cw20::msg::ExecuteMsg;
 
 impl IndexMessage for ExecuteMsg {
-    fn index_message(&self, _registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, _registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         debug!("StakeCw20ExecuteMsg index");
         dump_events(events);
         Ok(())
@@ -0,0 +1,117 @@
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::delegation::{
+    completion_time, insert_ledger_entries, reward_entries, LedgerEntry, DELEGATE, REDELEGATE_IN,
+    REDELEGATE_OUT, UNDELEGATE,
+};
+use crate::util::message_key::tx_failed;
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use cosmos_sdk_proto::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmos_sdk_proto::cosmos::staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate};
+use std::str::FromStr;
+
+fn coin_amount(coin: &Option<Coin>) -> anyhow::Result<(&str, BigDecimal)> {
+    let coin = coin.as_ref().ok_or_else(|| anyhow!("no amount"))?;
+    Ok((&coin.denom, BigDecimal::from_str(&coin.amount)?))
+}
+
+// Records the delegation changes a message made to its delegations with
+// `validators`, along with the rewards they withdrew.
+fn index_delegation_change(
+    registry: &IndexerRegistry,
+    events: &TxEvents,
+    delegator: &str,
+    validators: &[&str],
+    changes: impl FnOnce() -> anyhow::Result<Vec<LedgerEntry>>,
+) -> anyhow::Result<()> {
+    let db = match &registry.db {
+        Some(registry_db) => registry_db,
+        _ => return Ok(()),
+    };
+    if tx_failed(events) {
+        return Ok(());
+    }
+    let mut entries = changes()?;
+    entries.extend(reward_entries(events, delegator, validators)?);
+    insert_ledger_entries(db, events, delegator, &entries)
+}
+
+impl IndexMessage for MsgDelegate {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let delegator = self.delegator_address.as_str();
+        let validator = self.validator_address.as_str();
+        index_delegation_change(registry, events, delegator, &[validator], || {
+            let (denom, amount) = coin_amount(&self.amount)?;
+            Ok(vec![LedgerEntry::new(validator, DELEGATE, denom, amount)])
+        })
+    }
+}
+
+impl IndexMessage for MsgUndelegate {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let delegator = self.delegator_address.as_str();
+        let validator = self.validator_address.as_str();
+        index_delegation_change(registry, events, delegator, &[validator], || {
+            let (denom, amount) = coin_amount(&self.amount)?;
+            let completion_time = completion_time(
+                events,
+                "unbond",
+                delegator,
+                &[("validator", validator)],
+                denom,
+                &amount,
+            )?;
+            Ok(vec![LedgerEntry {
+                completion_time,
+                ..LedgerEntry::new(validator, UNDELEGATE, denom, amount)
+            }])
+        })
+    }
+}
+
+impl IndexMessage for MsgBeginRedelegate {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let delegator = self.delegator_address.as_str();
+        let source = self.validator_src_address.as_str();
+        let destination = self.validator_dst_address.as_str();
+        index_delegation_change(registry, events, delegator, &[source, destination], || {
+            let (denom, amount) = coin_amount(&self.amount)?;
+            let completion_time = completion_time(
+                events,
+                "redelegate",
+                delegator,
+                &[
+                    ("source_validator", source),
+                    ("destination_validator", destination),
+                ],
+                denom,
+                &amount,
+            )?;
+            let redelegate_out = LedgerEntry {
+                completion_time: completion_time.clone(),
+                ..LedgerEntry::new(source, REDELEGATE_OUT, denom, amount.clone())
+            };
+            let redelegate_in = LedgerEntry {
+                completion_time,
+                ..LedgerEntry::new(destination, REDELEGATE_IN, denom, amount)
+            };
+            Ok(vec![redelegate_out, redelegate_in])
+        })
+    }
+}
+
+impl IndexMessage for MsgWithdrawDelegatorReward {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let delegator = self.delegator_address.as_str();
+        index_delegation_change(
+            registry,
+            events,
+            delegator,
+            &[self.validator_address.as_str()],
+            || Ok(vec![]),
+        )
+    }
+}
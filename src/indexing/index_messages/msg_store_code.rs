@@ -0,0 +1,65 @@
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::contract_util::insert_code;
+use crate::util::message_key::{tx_failed, tx_height};
+use cosmrs::proto::cosmwasm::wasm::v1::MsgStoreCode;
+use flate2::read::GzDecoder;
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+// Uploads may be gzipped, which wasmd undoes before storing them.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The checksum wasmd gives stored code: the SHA-256 of the uncompressed
+/// wasm, in lowercase hex.
+pub fn wasm_checksum(wasm_byte_code: &[u8]) -> anyhow::Result<String> {
+    let digest = if wasm_byte_code.starts_with(&GZIP_MAGIC) {
+        let mut wasm = vec![];
+        GzDecoder::new(wasm_byte_code).read_to_end(&mut wasm)?;
+        Sha256::digest(&wasm)
+    } else {
+        Sha256::digest(wasm_byte_code)
+    };
+    Ok(format!("{:x}", digest))
+}
+
+impl IndexMessage for MsgStoreCode {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        let db = match &registry.db {
+            Some(registry_db) => registry_db,
+            _ => return Ok(()),
+        };
+        // A failed upload was given no code id.
+        if tx_failed(events) {
+            return Ok(());
+        }
+        // Older wasmd versions only report the code id on the message event.
+        let code_id: i64 = events
+            .first_attribute("store_code", "code_id")
+            .or_else(|_| events.first_attribute("message", "code_id"))?
+            .parse()?;
+        let checksum = match events.first_attribute("store_code", "code_checksum") {
+            Ok(checksum) => checksum.to_lowercase(),
+            Err(_) => wasm_checksum(&self.wasm_byte_code)?,
+        };
+        let height = tx_height(events)?;
+        debug!("{} stored code {} ({})", self.sender, code_id, checksum);
+        insert_code(db, code_id, &self.sender, &checksum, height)
+    }
+}
+
+#[test]
+fn test_wasm_checksum() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    // The smallest valid wasm module: its magic number and version.
+    let wasm = b"\0asm\x01\0\0\0";
+    let checksum = "93a44bbb96c751218e4c00d479e4c14358122a389acca16205b1e4d0dc5f9476";
+    assert_eq!(checksum, wasm_checksum(wasm).unwrap());
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(wasm).unwrap();
+    assert_eq!(checksum, wasm_checksum(&encoder.finish().unwrap()).unwrap());
+}
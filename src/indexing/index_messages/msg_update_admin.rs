@@ -0,0 +1,65 @@
+use crate::db::models::NewContractHistory;
+use crate::indexing::index_message::IndexMessage;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::contract_util::{insert_contract_history, update_contract_admin};
+use crate::util::message_key::{tx_failed, tx_height, MessageKey};
+use cosmrs::proto::cosmwasm::wasm::v1::{MsgClearAdmin, MsgUpdateAdmin};
+use log::debug;
+
+// Records that `sender` set the admin of `contract`, to no one if `admin` is
+// None.
+fn index_admin_change(
+    registry: &IndexerRegistry,
+    events: &TxEvents,
+    sender: &str,
+    contract: &str,
+    admin: Option<&str>,
+) -> anyhow::Result<()> {
+    let db = match &registry.db {
+        Some(registry_db) => registry_db,
+        _ => return Ok(()),
+    };
+    if tx_failed(events) {
+        return Ok(());
+    }
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    debug!("{} set the admin of {} to {:?}", sender, contract, admin);
+    insert_contract_history(
+        db,
+        &NewContractHistory {
+            contract_address: contract,
+            operation: if admin.is_some() {
+                "update_admin"
+            } else {
+                "clear_admin"
+            },
+            code_id: None,
+            admin,
+            sender,
+            height,
+            tx_hash: message_key.tx_hash,
+            msg_index: message_key.msg_index,
+        },
+    )?;
+    update_contract_admin(db, contract)
+}
+
+impl IndexMessage for MsgUpdateAdmin {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        index_admin_change(
+            registry,
+            events,
+            &self.sender,
+            &self.contract,
+            Some(&self.new_admin),
+        )
+    }
+}
+
+impl IndexMessage for MsgClearAdmin {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
+        index_admin_change(registry, events, &self.sender, &self.contract, None)
+    }
+}
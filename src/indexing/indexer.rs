@@ -1,6 +1,6 @@
 //! Indexer framework.
-use super::event_map::EventMap;
 use super::indexer_registry::{IndexerRegistry, RegistryKey};
+use super::tx_events::TxEvents;
 use crate::db::db_builder::DatabaseBuilder;
 use crate::indexing::index_message::IndexMessage;
 use log::{error, warn};
@@ -54,13 +54,14 @@ pub trait Indexer {
     /// # Arguments
     ///
     /// * `registry` - The registry of all indexers; serves as a global context
-    /// * `events` - All the transaction events in a map of "event.id": Vec<String> values.
+    /// * `events` - The message's events in the order they were emitted. They deref to the
+    ///   older map of "event.id": Vec<String> values.
     /// * `msg_dictionary` - Generic serde-parsed value dictionary object.
     /// * `msg_str` - The decoded raw string value of a message.
     fn index<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         msg_str: &'a str,
     ) -> anyhow::Result<()> {
@@ -78,7 +79,7 @@ pub trait Indexer {
     fn index_message_dictionary<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         msg_str: &'a str,
     ) -> anyhow::Result<()> {
@@ -146,7 +147,7 @@ pub trait IndexerDyn {
     fn index_dyn<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         msg_str: &'a str,
     ) -> anyhow::Result<()>;
@@ -163,7 +164,7 @@ impl<I: Indexer> IndexerDyn for I {
     fn index_dyn<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         msg_str: &'a str,
     ) -> anyhow::Result<()> {
@@ -1,12 +1,14 @@
-use super::event_map::EventMap;
 use super::indexer::{Indexer, IndexerDyn};
+use super::tx_events::TxEvents;
 use crate::db::db_builder::DatabaseBuilder;
 use crate::db::persister::{make_persister_ref, Persister, PersisterRef, StubPersister};
+use crate::metrics::INDEXER_MESSAGES;
+use crate::util::message_key::tx_failed;
 use diesel::pg::PgConnection;
 use log::{debug, error};
 use sea_orm::DatabaseConnection;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::ops::Deref;
 
@@ -50,6 +52,9 @@ pub struct IndexerRegistry {
     /// Maps string key values to ids of indexers
     handlers: HashMap<RegistryKey, Vec<usize>>,
     indexers: Vec<Box<dyn IndexerDyn>>,
+    /// Ids of the indexers that are handed messages from failed
+    /// transactions. No other indexer sees them.
+    failed_tx_indexers: HashSet<String>,
 }
 
 impl<'a> From<&'a IndexerRegistry> for &'a PgConnection {
@@ -87,9 +92,21 @@ impl<'a> IndexerRegistry {
             handlers: HashMap::default(),
             indexers: vec![],
             persister,
+            failed_tx_indexers: HashSet::default(),
         }
     }
 
+    /// Lets the indexers with these ids see messages from failed
+    /// transactions.
+    pub fn set_failed_tx_indexers(&mut self, indexer_ids: &[String]) {
+        self.failed_tx_indexers = indexer_ids.iter().cloned().collect();
+    }
+
+    /// Whether any indexer wants messages from failed transactions.
+    pub fn indexes_failed_txs(&self) -> bool {
+        !self.failed_tx_indexers.is_empty()
+    }
+
     pub fn initialize(&mut self) -> anyhow::Result<()> {
         for indexer in self.indexers.iter() {
             indexer.initialize_dyn(self)?;
@@ -105,17 +122,25 @@ impl<'a> IndexerRegistry {
     // and asks its registered indexers to index it if they can.
     pub fn index_message_and_events(
         &self,
-        events: &EventMap,
+        events: &TxEvents,
         msg_dictionary: &Value,
         msg_str: &str,
     ) -> anyhow::Result<()> {
+        let failed = tx_failed(events);
         if let Some(message_keys) = &self.extract_message_keys(msg_dictionary, msg_str) {
             for message_key in message_keys {
                 if let Some(handlers) = self.indexers_for_key(message_key) {
                     for handler_id in handlers {
                         if let Some(indexer) = self.indexers.get(*handler_id) {
-                            if let Err(e) = indexer.index_dyn(self, events, msg_dictionary, msg_str)
-                            {
+                            if failed && !self.failed_tx_indexers.contains(&indexer.id()) {
+                                continue;
+                            }
+                            let result = indexer.index_dyn(self, events, msg_dictionary, msg_str);
+                            let outcome = if result.is_ok() { "success" } else { "failure" };
+                            INDEXER_MESSAGES
+                                .with_label_values(&[&indexer.id(), outcome])
+                                .inc();
+                            if let Err(e) = result {
                                 eprintln!("ERROR: {}", e);
                                 error!(
                                     "Error indexing message:\n{:#?}\n{:#?}\n{:#?}\n{:#?}",
@@ -203,7 +228,7 @@ impl Indexer for TestIndexer {
     fn index<'b>(
         &self,
         _registry: &'b IndexerRegistry,
-        _events: &'b EventMap,
+        _events: &'b TxEvents,
         _msg_dictionary: &'b Value,
         _msg_str: &'b str,
     ) -> anyhow::Result<()> {
@@ -1,12 +1,13 @@
-use crate::indexing::event_map::EventMap;
 use crate::indexing::index_message::IndexMessage;
 use crate::indexing::indexer::{
     registry_keys_from_iter, root_keys_from_iter, Indexer, RegistryKeysType, RootKeysType,
 };
 use crate::indexing::indexer_registry::{IndexerRegistry, RegistryKey};
+use crate::indexing::tx_events::TxEvents;
 use crate::util::contract_util::get_contract_addresses;
 use crate::util::dao::{get_single_event_item, get_tx_height_from_events, insert_dao};
 use crate::util::gov_token::gov_token_from_msg;
+use crate::util::message_key::MessageKey;
 use cw3_dao::msg::ExecuteMsg as Cw3DaoExecuteMsg;
 use cw3_dao::msg::InstantiateMsg as Cw3DaoInstantiateMsg;
 use cw3_dao_2_5::msg::InstantiateMsg as Cw3DaoInstantiateMsg25;
@@ -133,8 +134,8 @@ impl Indexer for Cw3DaoInstantiateMsgIndexer {
         &'a self,
         // The registry of indexers
         registry: &'a IndexerRegistry,
-        // All the transaction events in a map of "event.id": Vec<String> values.
-        events: &'a EventMap,
+        // The message's events, in the order they were emitted.
+        events: &'a TxEvents,
         // Generic serde-parsed value dictionary
         msg_dictionary: &'a Value,
         // The decoded string value of the message
@@ -156,7 +157,7 @@ impl Indexer for Cw3DaoInstantiateMsgIndexer {
     fn index_message_dictionary<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         _msg_str: &'a str,
     ) -> anyhow::Result<()> {
@@ -185,6 +186,7 @@ impl Indexer for Cw3DaoInstantiateMsgIndexer {
                 image_url,
                 &contract_addresses,
                 Some(&tx_height),
+                &MessageKey::from_events(events, 0),
             )
         } else {
             error!("Could not parse GovTokenMsg from {:#?}", msg_dictionary);
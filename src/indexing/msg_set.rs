@@ -1,3 +1,4 @@
+use crate::metrics::UNREGISTERED_MSGS;
 use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
@@ -31,6 +32,7 @@ impl MsgSetInternal {
             result = self.unregistered_msgs.contains(msg);
             if !result {
                 self.unregistered_msgs.insert(msg.to_string());
+                UNREGISTERED_MSGS.set(self.unregistered_msgs.len() as i64);
                 result = true;
             }
         }
@@ -46,38 +48,20 @@ impl Default for MsgSetInternal {
 
 fn init_known_unknown_messages(msg_set: &mut MsgMap) {
     let known = [
-        "/cosmos.authz.v1beta1.MsgExec",
-        "/cosmos.authz.v1beta1.MsgGrant",
-        "/cosmos.authz.v1beta1.MsgRevoke",
         "/cosmos.distribution.v1beta1.MsgFundCommunityPool",
         "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress",
-        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
         "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
         "/cosmos.feegrant.v1beta1.MsgGrantAllowance",
         "/cosmos.feegrant.v1beta1.MsgRevokeAllowance",
-        "/cosmos.gov.v1beta1.MsgDeposit",
-        "/cosmos.gov.v1beta1.MsgSubmitProposal",
-        "/cosmos.gov.v1beta1.MsgVote",
-        "/cosmos.gov.v1beta1.MsgVoteWeighted",
         "/cosmos.slashing.v1beta1.MsgUnjail",
-        "/cosmos.staking.v1beta1.MsgBeginRedelegate",
         "/cosmos.staking.v1beta1.MsgCreateValidator",
-        "/cosmos.staking.v1beta1.MsgDelegate",
         "/cosmos.staking.v1beta1.MsgEditValidator",
-        "/cosmos.staking.v1beta1.MsgUndelegate",
         "/cosmos.staking.v1beta1.MsgWithdrawDelegatorReward",
         "/cosmos.staking.v1beta1.MsgWithdrawValidatorCommission",
-        "/cosmwasm.wasm.v1.MsgMigrateContract",
-        "/cosmwasm.wasm.v1.MsgStoreCode",
-        "/cosmwasm.wasm.v1.MsgUpdateAdmin",
-        "/ibc.applications.transfer.v1.MsgTransfer",
-        "/ibc.core.channel.v1.MsgAcknowledgement",
         "/ibc.core.channel.v1.MsgChannelOpenAck",
         "/ibc.core.channel.v1.MsgChannelOpenConfirm",
         "/ibc.core.channel.v1.MsgChannelOpenInit",
         "/ibc.core.channel.v1.MsgChannelOpenTry",
-        "/ibc.core.channel.v1.MsgRecvPacket",
-        "/ibc.core.channel.v1.MsgTimeout",
         "/ibc.core.client.v1.MsgCreateClient",
         "/ibc.core.client.v1.MsgUpdateClient",
         "/ibc.core.connection.v1.MsgConnectionOpenAck",
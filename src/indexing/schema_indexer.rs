@@ -4,17 +4,19 @@ use crate::db::db_builder::DatabaseBuilder;
 use crate::db::db_mapper::FieldMappingPolicy;
 use crate::db::persister::PersisterRef;
 
-use super::event_map::EventMap;
 use super::index_message::IndexMessage;
 use super::indexer::{
     registry_keys_from_iter, root_keys_from_iter, Indexer, RegistryKeysType, RootKeysType,
 };
 use super::indexer_registry::{IndexerRegistry, RegistryKey};
+use super::tx_events::TxEvents;
 
 use serde::{Deserialize, Serialize};
 
 use crate::db::db_util::foreign_key;
+use crate::util::message_key::MessageKey;
 use anyhow::anyhow;
+use futures::executor::block_on;
 use log::{debug, warn};
 use schemars::schema::{
     InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec,
@@ -30,7 +32,7 @@ pub struct SchemaIndexerGenericMessage {}
 impl IndexMessage for SchemaIndexerGenericMessage {
     // This is a stub message; unlike the IndexMessage implemented for sepcific
     // messages, the SchemaIndexer itself performs indexing on its messages.
-    fn index_message(&self, registry: &IndexerRegistry, events: &EventMap) -> anyhow::Result<()> {
+    fn index_message(&self, registry: &IndexerRegistry, events: &TxEvents) -> anyhow::Result<()> {
         Ok(())
     }
 }
@@ -455,17 +457,20 @@ impl Indexer for SchemaIndexer<u64> {
     fn index<'a>(
         &'a self,
         registry: &'a IndexerRegistry,
-        _events: &'a EventMap,
+        events: &'a TxEvents,
         msg_dictionary: &'a Value,
         _msg_str: &'a str,
     ) -> anyhow::Result<()> {
         if let Some(persister) = self.persister.try_write() {
-            registry.db_builder.value_mapper.persist_message(
+            let message_key = MessageKey::from_events(events, 0);
+            // Indexers are synchronous, so the write is finished here.
+            block_on(registry.db_builder.value_mapper.persist_message_with_key(
                 persister.borrow().as_ref(),
                 &self.id,
                 msg_dictionary,
                 None,
-            );
+                Some(&message_key),
+            ))?;
             Ok(())
         } else {
             Err(anyhow::anyhow!("unable to get write lock"))
@@ -2,17 +2,20 @@
 pub mod tests {
     use crate::db::db_persister::DatabasePersister;
     use crate::db::persister::{make_persister_ref, Persister};
+    use crate::indexing::indexer::IndexerDyn;
     use crate::indexing::indexer_registry::IndexerRegistry;
     use crate::indexing::schema_indexer::*;
+    use async_trait::async_trait;
     use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction};
     use serde::{Deserialize, Serialize};
+    use std::sync::{Arc, Mutex};
     use tokio::test;
 
     use crate::db::db_builder::DatabaseBuilder;
     use crate::db::db_test::compare_table_create_statements;
     use crate::db::persister::PersisterRef;
 
-    use crate::indexing::event_map::EventMap;
+    use crate::indexing::tx_events::TxEvents;
 
     use log::debug;
     use schemars::schema::RootSchema;
@@ -236,7 +239,8 @@ pub mod tests {
             "simple_field_two": 33,
         });
         let msg_str = serde_json::to_string(&msg_dictionary)?;
-        let result = registry.index_message_and_events(&EventMap::new(), &msg_dictionary, &msg_str);
+        let result =
+            registry.index_message_and_events(&TxEvents::default(), &msg_dictionary, &msg_str);
         assert!(result.is_ok());
 
         let expected_transaction_log = vec![sea_orm::Transaction::from_sql_and_values(
@@ -311,7 +315,8 @@ pub mod tests {
             }
         });
         let msg_str = serde_json::to_string(&msg_dictionary)?;
-        let result = registry.index_message_and_events(&EventMap::new(), &msg_dictionary, &msg_str);
+        let result =
+            registry.index_message_and_events(&TxEvents::default(), &msg_dictionary, &msg_str);
         assert!(result.is_ok());
 
         let expected_transaction_log = vec![
@@ -436,7 +441,8 @@ pub mod tests {
         };
         let msg_str = serde_json::to_string(&native_simple_related_message).unwrap();
         let msg_dictionary = serde_json::from_str(&msg_str).unwrap();
-        let result = registry.index_message_and_events(&EventMap::new(), &msg_dictionary, &msg_str);
+        let result =
+            registry.index_message_and_events(&TxEvents::default(), &msg_dictionary, &msg_str);
         assert!(result.is_ok());
 
         // TODO: this is missing creation of related records, and should fail comparison.
@@ -473,6 +479,73 @@ pub mod tests {
         Ok(())
     }
 
+    // Records the tables and columns saved to.
+    #[derive(Debug, Default)]
+    struct RecordingPersister {
+        saved: Arc<Mutex<Vec<(String, Vec<String>)>>>,
+    }
+
+    #[async_trait]
+    impl Persister for RecordingPersister {
+        type Id = u64;
+        async fn save<'a>(
+            &'a self,
+            table_name: &'a str,
+            column_names: &'a [&'a str],
+            _values: &'a [&'a Value],
+            _id: Option<Self::Id>,
+        ) -> anyhow::Result<Self::Id> {
+            let mut saved = self.saved.lock().unwrap();
+            saved.push((
+                table_name.to_string(),
+                column_names.iter().map(|name| name.to_string()).collect(),
+            ));
+            Ok(saved.len() as u64)
+        }
+    }
+
+    #[test]
+    async fn test_index_writes_keyed_message() -> anyhow::Result<()> {
+        use crate::indexing::tx_events::TxEvent;
+        use schemars::schema_for;
+
+        let name = stringify!(SimpleMessage);
+        let saved = Arc::new(Mutex::new(vec![]));
+        let persister = RecordingPersister {
+            saved: saved.clone(),
+        };
+        let persister_ref = make_persister_ref(Box::new(persister));
+        let result = get_test_registry(name, schema_for!(SimpleMessage), None, Some(persister_ref));
+        let indexer_id = result._indexer_id;
+        let mut registry = result.registry;
+        assert!(registry.initialize().is_ok(), "failed to init indexer");
+
+        let events = TxEvents::new(vec![TxEvent::new(
+            "tx",
+            vec![
+                ("hash".to_string(), "ABCDEF".to_string()),
+                ("msg_index".to_string(), "0".to_string()),
+            ],
+        )]);
+        let msg_dictionary = serde_json::json!({
+            "simple_field_one": "simple_field_one value",
+            "simple_field_two": 33,
+        });
+        let msg_str = serde_json::to_string(&msg_dictionary)?;
+        // Called directly, as the registry only logs indexing errors.
+        let indexer = registry.get_indexer(indexer_id).unwrap();
+        indexer.index_dyn(&registry, &events, &msg_dictionary, &msg_str)?;
+
+        let saved = saved.lock().unwrap();
+        assert_eq!(1, saved.len());
+        let (table_name, column_names) = &saved[0];
+        assert_eq!(name, table_name);
+        for key_column in ["tx_hash", "msg_index", "event_index"] {
+            assert!(column_names.iter().any(|column| column == key_column));
+        }
+        Ok(())
+    }
+
     #[test]
     async fn test_deserialize() {
         // use schemars::schema_for;
@@ -2,102 +2,313 @@ use super::event_map::EventMap;
 use super::index_message::IndexMessage;
 use super::indexer_registry::IndexerRegistry;
 use super::msg_set::MsgSet;
+use super::tx_events::{TxEvent, TxEvents};
+use crate::metrics::MESSAGES_PROCESSED;
 use anyhow::anyhow;
 use cosmrs::cosmwasm::MsgInstantiateContract;
-use cosmrs::proto::cosmos::bank::v1beta1::MsgSend;
+use cosmrs::proto::cosmos::authz::v1beta1::{MsgExec, MsgGrant, MsgRevoke};
+use cosmrs::proto::cosmos::bank::v1beta1::{MsgMultiSend, MsgSend};
+use cosmrs::proto::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmrs::proto::cosmos::gov::v1beta1::{
+    MsgDeposit, MsgSubmitProposal, MsgVote, MsgVoteWeighted,
+};
+use cosmrs::proto::cosmos::staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate};
 use cosmrs::proto::cosmwasm::wasm::v1::{
-    MsgExecuteContract, MsgInstantiateContract as ProtoMsgInstContrct,
+    MsgClearAdmin, MsgExecuteContract, MsgInstantiateContract as ProtoMsgInstContrct,
+    MsgMigrateContract, MsgStoreCode, MsgUpdateAdmin,
 };
+use cosmrs::proto::ibc::applications::transfer::v1::MsgTransfer;
+use cosmrs::proto::ibc::core::channel::v1::{MsgAcknowledgement, MsgRecvPacket, MsgTimeout};
 use cosmrs::tx::{MsgProto, Tx};
+use diesel::Connection;
 use log::{debug, error};
+use prost::Message;
 use prost_types::Any;
 use tendermint_rpc::event::TxInfo;
 
 pub fn process_parsed(
     registry: &IndexerRegistry,
     tx_parsed: &Tx,
-    events: &EventMap,
+    events: &TxEvents,
+    message_events: &[TxEvents],
     msg_set: MsgSet,
-) -> anyhow::Result<()> {
-    process_messages(registry, &tx_parsed.body.messages, events, msg_set)
+) -> anyhow::Result<MessageIndexSummary> {
+    process_messages(
+        registry,
+        &tx_parsed.body.messages,
+        events,
+        message_events,
+        msg_set,
+    )
 }
 
 pub fn process_parsed_v1beta(
     registry: &IndexerRegistry,
     tx_parsed: &cosmos_sdk_proto::cosmos::tx::v1beta1::Tx,
-    events: &EventMap,
+    events: &TxEvents,
+    message_events: &[TxEvents],
     msg_set: MsgSet,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<MessageIndexSummary> {
     if let Some(body) = &tx_parsed.body {
-        process_messages(registry, &body.messages, events, msg_set)
+        process_messages(registry, &body.messages, events, message_events, msg_set)
     } else {
-        Ok(())
+        Ok(MessageIndexSummary::default())
+    }
+}
+
+/// A message of a transaction that couldn't be indexed.
+#[derive(Debug)]
+pub struct MessageFailure {
+    pub msg_index: usize,
+    pub type_url: String,
+    pub error: anyhow::Error,
+}
+
+/// What became of each message of a transaction.
+#[derive(Debug, Default)]
+pub struct MessageIndexSummary {
+    /// Messages handed to a handler that indexed them.
+    pub indexed: usize,
+    /// Messages of a type nothing indexes.
+    pub unhandled: usize,
+    pub failures: Vec<MessageFailure>,
+}
+
+impl MessageIndexSummary {
+    pub fn total(&self) -> usize {
+        self.indexed + self.unhandled + self.failures.len()
     }
 }
 
+/// Indexes the messages of a transaction, in order. `events` are everything
+/// the transaction emitted and `message_events` what each message emitted,
+/// if known; each message is indexed with its own events.
+///
+/// A message that fails is recorded in the summary and the rest are still
+/// indexed. With a database, each message runs in its own savepoint so a
+/// failed one leaves nothing behind.
 pub fn process_messages(
     registry: &IndexerRegistry,
     messages: &[Any],
-    events: &EventMap,
+    events: &TxEvents,
+    message_events: &[TxEvents],
     msg_set: MsgSet,
-) -> anyhow::Result<()> {
-    for msg in messages.iter() {
-        let type_url: &str = &msg.type_url;
-        debug!("processing msg {:?}", msg);
-        match type_url {
-            "/cosmwasm.wasm.v1.MsgInstantiateContract" => {
-                match ProtoMsgInstContrct::from_any(msg) {
-                    Ok(proto_msg_instantiate_contract) => {
-                        match MsgInstantiateContract::try_from(proto_msg_instantiate_contract) {
-                            Ok(msg_inst_contract) => {
-                                return msg_inst_contract.index_message(registry, events);
-                            }
-                            Err(e) => {
-                                error!(
-                                    "error parsing MsgInstantiateContract, events: {:?}",
-                                    events
-                                );
-                                return Err(anyhow!(e));
-                            }
-                        }
-                    }
+) -> anyhow::Result<MessageIndexSummary> {
+    let mut summary = MessageIndexSummary::default();
+    let mut next_wrapped_index = messages.len();
+    for (msg_index, msg) in messages.iter().enumerate() {
+        let events = &events.for_message(message_events, msg_index, messages.len());
+        let first_wrapped_index = next_wrapped_index;
+        next_wrapped_index += wrapped_message_count(msg);
+        debug!("processing msg {} {:?}", msg_index, msg);
+        MESSAGES_PROCESSED.inc();
+        let index = || index_msg(registry, msg, events, msg_set.clone(), first_wrapped_index);
+        let result = match &registry.db {
+            Some(db) => db.transaction(index),
+            None => index(),
+        };
+        match result {
+            Ok(true) => summary.indexed += 1,
+            Ok(false) => summary.unhandled += 1,
+            Err(error) => {
+                error!(
+                    "Error indexing msg {} ({}): {:?}",
+                    msg_index, msg.type_url, error
+                );
+                summary.failures.push(MessageFailure {
+                    msg_index,
+                    type_url: msg.type_url.clone(),
+                    error,
+                });
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// How many messages `msg` wraps, directly or not. Wrapped messages are
+// numbered after the top-level messages of their transaction, each
+// message's in turn, so every message has its own index for natural keys.
+fn wrapped_message_count(msg: &Any) -> usize {
+    if msg.type_url != "/cosmos.authz.v1beta1.MsgExec" {
+        return 0;
+    }
+    match MsgExec::decode(msg.value.as_slice()) {
+        Ok(msg_exec) => msg_exec
+            .msgs
+            .iter()
+            .map(|wrapped_msg| 1 + wrapped_message_count(wrapped_msg))
+            .sum(),
+        Err(_) => 0,
+    }
+}
+
+// The messages an authz MsgExec wraps, each with the events it is indexed
+// with and the index the messages it wraps in turn are numbered from. The
+// wrapped messages themselves are numbered from `first_wrapped_index`.
+fn wrapped_messages<'a>(
+    msg_exec: &'a MsgExec,
+    events: &TxEvents,
+    first_wrapped_index: usize,
+) -> Vec<(&'a Any, TxEvents, usize)> {
+    // The wrapped messages are indexed as though their signers had sent
+    // them, with the grantee that executed them recorded.
+    let mut exec_events = events.clone();
+    exec_events.push(TxEvent::new(
+        "tx",
+        vec![("executor".to_string(), msg_exec.grantee.clone())],
+    ));
+    let split_events = exec_events.split_wrapped(msg_exec.msgs.len());
+    if split_events.is_none() {
+        debug!(
+            "events of the messages executed by {} can't be told apart",
+            msg_exec.grantee
+        );
+    }
+    let mut next_wrapped_index = first_wrapped_index + msg_exec.msgs.len();
+    let mut wrapped = vec![];
+    for (position, wrapped_msg) in msg_exec.msgs.iter().enumerate() {
+        let wrapped_events = exec_events.for_wrapped_message(
+            split_events.as_ref().map(|split| &split[position]),
+            first_wrapped_index + position,
+        );
+        wrapped.push((wrapped_msg, wrapped_events, next_wrapped_index));
+        next_wrapped_index += wrapped_message_count(wrapped_msg);
+    }
+    wrapped
+}
+
+// Indexes one message. Returns whether there was a handler for it, or for
+// any of the messages it wraps.
+fn index_msg(
+    registry: &IndexerRegistry,
+    msg: &Any,
+    events: &TxEvents,
+    msg_set: MsgSet,
+    first_wrapped_index: usize,
+) -> anyhow::Result<bool> {
+    let type_url: &str = &msg.type_url;
+    match type_url {
+        "/cosmwasm.wasm.v1.MsgInstantiateContract" => match ProtoMsgInstContrct::from_any(msg) {
+            Ok(proto_msg_instantiate_contract) => {
+                match MsgInstantiateContract::try_from(proto_msg_instantiate_contract) {
+                    Ok(msg_inst_contract) => msg_inst_contract.index_message(registry, events)?,
                     Err(e) => {
-                        error!(
-                            "error parsing ProstMsgInstantiateContract, events: {:?}",
-                            events
-                        );
+                        error!("error parsing MsgInstantiateContract, events: {:?}", events);
                         return Err(anyhow!(e));
                     }
                 }
             }
-            "/cosmwasm.wasm.v1.MsgExecuteContract" => match MsgExecuteContract::from_any(msg) {
-                Ok(msg_obj) => {
-                    return msg_obj.index_message(registry, events);
-                }
-                Err(e) => {
-                    error!("error parsing MsgExecuteContract, events: {:?}", events);
-                    return Err(anyhow!(e));
-                }
-            },
-            "/cosmos.bank.v1beta1.MsgSend" => match MsgSend::from_any(msg) {
-                Ok(msg_obj) => {
-                    return msg_obj.index_message(registry, events);
-                }
-                Err(e) => {
-                    error!("error parsing MsgSend, events: {:?}", events);
-                    return Err(anyhow!(e));
-                }
-            },
-            _ => {
-                let mut msg_set_writable = msg_set.lock().unwrap();
-                let found = msg_set_writable.validate(type_url);
-                if !found {
-                    error!("No handler for {}", type_url);
-                }
+            Err(e) => {
+                error!(
+                    "error parsing ProstMsgInstantiateContract, events: {:?}",
+                    events
+                );
+                return Err(anyhow!(e));
             }
+        },
+        "/cosmwasm.wasm.v1.MsgExecuteContract" => match MsgExecuteContract::from_any(msg) {
+            Ok(msg_obj) => msg_obj.index_message(registry, events)?,
+            Err(e) => {
+                error!("error parsing MsgExecuteContract, events: {:?}", events);
+                return Err(anyhow!(e));
+            }
+        },
+        "/cosmwasm.wasm.v1.MsgStoreCode" => {
+            MsgStoreCode::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmwasm.wasm.v1.MsgMigrateContract" => {
+            MsgMigrateContract::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmwasm.wasm.v1.MsgUpdateAdmin" => {
+            MsgUpdateAdmin::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmwasm.wasm.v1.MsgClearAdmin" => {
+            MsgClearAdmin::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.authz.v1beta1.MsgExec" => {
+            let msg_exec = MsgExec::decode(msg.value.as_slice())?;
+            let mut handled = false;
+            for (wrapped_msg, wrapped_events, first_wrapped_index) in
+                wrapped_messages(&msg_exec, events, first_wrapped_index)
+            {
+                debug!(
+                    "processing msg executed by {}: {:?}",
+                    msg_exec.grantee, wrapped_msg
+                );
+                handled |= index_msg(
+                    registry,
+                    wrapped_msg,
+                    &wrapped_events,
+                    msg_set.clone(),
+                    first_wrapped_index,
+                )?;
+            }
+            return Ok(handled);
+        }
+        "/cosmos.authz.v1beta1.MsgGrant" => {
+            MsgGrant::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.authz.v1beta1.MsgRevoke" => {
+            MsgRevoke::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.gov.v1beta1.MsgSubmitProposal" => {
+            MsgSubmitProposal::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.gov.v1beta1.MsgDeposit" => {
+            MsgDeposit::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.gov.v1beta1.MsgVote" => {
+            MsgVote::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.gov.v1beta1.MsgVoteWeighted" => {
+            MsgVoteWeighted::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.staking.v1beta1.MsgDelegate" => {
+            MsgDelegate::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.staking.v1beta1.MsgUndelegate" => {
+            MsgUndelegate::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.staking.v1beta1.MsgBeginRedelegate" => {
+            MsgBeginRedelegate::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward" => {
+            MsgWithdrawDelegatorReward::decode(msg.value.as_slice())?
+                .index_message(registry, events)?
+        }
+        "/ibc.applications.transfer.v1.MsgTransfer" => {
+            MsgTransfer::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/ibc.core.channel.v1.MsgRecvPacket" => {
+            MsgRecvPacket::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/ibc.core.channel.v1.MsgAcknowledgement" => {
+            MsgAcknowledgement::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/ibc.core.channel.v1.MsgTimeout" => {
+            MsgTimeout::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.bank.v1beta1.MsgMultiSend" => {
+            MsgMultiSend::decode(msg.value.as_slice())?.index_message(registry, events)?
+        }
+        "/cosmos.bank.v1beta1.MsgSend" => match MsgSend::from_any(msg) {
+            Ok(msg_obj) => msg_obj.index_message(registry, events)?,
+            Err(e) => {
+                error!("error parsing MsgSend, events: {:?}", events);
+                return Err(anyhow!(e));
+            }
+        },
+        _ => {
+            let mut msg_set_writable = msg_set.lock().unwrap();
+            let found = msg_set_writable.validate(type_url);
+            if !found {
+                error!("No handler for {}", type_url);
+            }
+            return Ok(false);
         }
     }
-    Ok(())
+    Ok(true)
 }
 
 pub fn process_tx_info(
@@ -105,7 +316,157 @@ pub fn process_tx_info(
     tx_info: TxInfo,
     events: &EventMap,
     msg_set: MsgSet,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<MessageIndexSummary> {
     let tx_parsed = Tx::from_bytes(&tx_info.tx).map_err(|e| anyhow!(e))?;
-    process_parsed(registry, &tx_parsed, events, msg_set)
+    let message_events = TxEvents::from_message_logs(&tx_info.result.log.unwrap_or_default());
+    process_parsed(
+        registry,
+        &tx_parsed,
+        &TxEvents::from_event_map(events),
+        &message_events,
+        msg_set,
+    )
+}
+
+#[test]
+fn test_process_messages_isolates_failures() {
+    use super::msg_set::default_msg_set;
+    use cosmrs::proto::cosmos::base::v1beta1::Coin;
+    let registry = IndexerRegistry::default();
+    let send = MsgSend {
+        from_address: "juno1sender".to_string(),
+        to_address: "juno1recipient".to_string(),
+        amount: vec![Coin {
+            denom: "ujuno".to_string(),
+            amount: "10".to_string(),
+        }],
+    };
+    let bad_execute = MsgExecuteContract {
+        sender: "juno1sender".to_string(),
+        contract: "juno1contract".to_string(),
+        msg: b"not json".to_vec(),
+        funds: vec![],
+    };
+    // A message no handler indexes.
+    let unjail = Any {
+        type_url: "/cosmos.slashing.v1beta1.MsgUnjail".to_string(),
+        value: vec![],
+    };
+    let messages = vec![
+        send.to_any().unwrap(),
+        bad_execute.to_any().unwrap(),
+        send.to_any().unwrap(),
+        unjail.clone(),
+    ];
+    let summary = process_messages(
+        &registry,
+        &messages,
+        &TxEvents::default(),
+        &[],
+        default_msg_set(),
+    )
+    .unwrap();
+    // The failed second message doesn't stop the third.
+    assert_eq!(2, summary.indexed);
+    assert_eq!(1, summary.unhandled);
+    assert_eq!(1, summary.failures.len());
+    assert_eq!(1, summary.failures[0].msg_index);
+    assert_eq!(
+        "/cosmwasm.wasm.v1.MsgExecuteContract",
+        summary.failures[0].type_url
+    );
+    assert_eq!(4, summary.total());
+
+    // Messages executed through authz are indexed like any other.
+    let exec = MsgExec {
+        grantee: "juno1grantee".to_string(),
+        msgs: vec![send.to_any().unwrap(), unjail],
+    };
+    let exec = Any {
+        type_url: "/cosmos.authz.v1beta1.MsgExec".to_string(),
+        value: exec.encode_to_vec(),
+    };
+    let summary = process_messages(
+        &registry,
+        &[exec],
+        &TxEvents::default(),
+        &[],
+        default_msg_set(),
+    )
+    .unwrap();
+    assert_eq!(1, summary.indexed);
+    assert!(summary.failures.is_empty());
+}
+
+#[test]
+fn test_wrapped_messages() {
+    use crate::util::message_key::{executor, MessageKey};
+    use cosmrs::proto::cosmos::base::v1beta1::Coin;
+    let delegate = |validator: &str| Any {
+        type_url: "/cosmos.staking.v1beta1.MsgDelegate".to_string(),
+        value: MsgDelegate {
+            delegator_address: "juno1delegator".to_string(),
+            validator_address: validator.to_string(),
+            amount: Some(Coin {
+                denom: "ujuno".to_string(),
+                amount: "10".to_string(),
+            }),
+        }
+        .encode_to_vec(),
+    };
+    let msg_exec = MsgExec {
+        grantee: "juno1restake".to_string(),
+        msgs: vec![delegate("junovaloper1a"), delegate("junovaloper1b")],
+    };
+    let event = |type_str: &str, attributes: &[(&str, &str)]| {
+        TxEvent::new(
+            type_str,
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    };
+    let tx_events = TxEvents::new(vec![
+        event("tx", &[("hash", "ABCDEF")]),
+        event("message", &[("action", "/cosmos.authz.v1beta1.MsgExec")]),
+        event("delegate", &[("validator", "junovaloper1a")]),
+        event("message", &[("module", "staking")]),
+        event("delegate", &[("validator", "junovaloper1b")]),
+        event("message", &[("module", "staking")]),
+    ]);
+    let events = tx_events.for_message(&tx_events.split_by_action(), 0, 1);
+    let wrapped = wrapped_messages(&msg_exec, &events, 1);
+    assert_eq!(2, wrapped.len());
+    for (position, (_, wrapped_events, first_wrapped_index)) in wrapped.iter().enumerate() {
+        // Each message has its own key and sees only its own delegation.
+        assert_eq!(
+            MessageKey::new(Some("ABCDEF".to_string()), position as i32 + 1, 0),
+            MessageKey::from_events(wrapped_events, 0)
+        );
+        let validators: Vec<&str> = wrapped_events
+            .of_type("delegate")
+            .filter_map(|event| event.attribute("validator"))
+            .collect();
+        assert_eq!(
+            vec![["junovaloper1a", "junovaloper1b"][position]],
+            validators
+        );
+        assert_eq!(Some("juno1restake"), executor(wrapped_events));
+        assert_eq!(3, *first_wrapped_index);
+    }
+
+    // Wrapped messages are numbered after the transaction's own.
+    let exec = Any {
+        type_url: "/cosmos.authz.v1beta1.MsgExec".to_string(),
+        value: msg_exec.encode_to_vec(),
+    };
+    assert_eq!(2, wrapped_message_count(&exec));
+    let nested = MsgExec {
+        grantee: "juno1outer".to_string(),
+        msgs: vec![exec, delegate("junovaloper1c")],
+    };
+    let wrapped = wrapped_messages(&nested, &events, 1);
+    let first_wrapped_indices: Vec<usize> = wrapped.iter().map(|(_, _, index)| *index).collect();
+    assert_eq!(vec![3, 5], first_wrapped_indices);
 }
@@ -0,0 +1,620 @@
+use super::event_map::EventMap;
+use anyhow::anyhow;
+use serde::Deserialize;
+use std::ops::Deref;
+use tendermint::abci::responses::Event;
+
+// The attribute cosmwasm adds to every wasm event naming the contract that
+// emitted it.
+const CONTRACT_ADDRESS_KEY: &str = "_contract_address";
+
+// The attribute newer chains add to the events of each message an authz
+// MsgExec dispatches, giving the message's position within the exec.
+const AUTHZ_MSG_INDEX_KEY: &str = "authz_msg_index";
+
+/// One event emitted by a transaction, with its attributes in the order
+/// they were emitted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxEvent {
+    pub type_str: String,
+    pub attributes: Vec<(String, String)>,
+    /// The contract that emitted the event, for wasm events.
+    pub contract_address: Option<String>,
+}
+
+impl TxEvent {
+    pub fn new(type_str: &str, attributes: Vec<(String, String)>) -> Self {
+        let contract_address = attributes
+            .iter()
+            .find(|(key, _)| key == CONTRACT_ADDRESS_KEY)
+            .map(|(_, value)| value.clone());
+        TxEvent {
+            type_str: type_str.to_string(),
+            attributes,
+            contract_address,
+        }
+    }
+
+    /// The value of the first attribute named `key`.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes(key).next()
+    }
+
+    /// Every value of the attributes named `key`, in order.
+    pub fn attributes<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.attributes
+            .iter()
+            .filter(move |(attribute_key, _)| attribute_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value of the first attribute named `key`, or an error if there
+    /// is none.
+    pub fn require(&self, key: &str) -> anyhow::Result<&str> {
+        self.attribute(key)
+            .ok_or_else(|| anyhow!("no {} in {} event", key, self.type_str))
+    }
+}
+
+/// The events of a transaction, or of one of its messages, in the order
+/// they were emitted.
+///
+/// Derefs to the flattened `EventMap` that older code reads, where each
+/// "type.key" holds the values of that attribute across every event of
+/// the type.
+#[derive(Clone, Debug, Default)]
+pub struct TxEvents {
+    events: Vec<TxEvent>,
+    event_map: EventMap,
+    // Whether these are a message's events mixed with other messages'.
+    shared: bool,
+}
+
+// One message's entry in a successful transaction's ABCI log, which is a
+// JSON list of these.
+#[derive(Deserialize)]
+struct MessageLog {
+    // Left out of the JSON for the first message.
+    #[serde(default)]
+    msg_index: usize,
+    #[serde(default)]
+    events: Vec<LogEvent>,
+}
+
+#[derive(Deserialize)]
+struct LogEvent {
+    #[serde(rename = "type")]
+    type_str: String,
+    #[serde(default)]
+    attributes: Vec<LogAttribute>,
+}
+
+#[derive(Deserialize)]
+struct LogAttribute {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+// Logs merge every event of a type into one, so a wasm event in a log holds
+// the attributes of each contract that was called, each contract's starting
+// with its address. This splits them back up.
+fn split_by_contract(type_str: &str, attributes: Vec<(String, String)>) -> Vec<TxEvent> {
+    let mut groups: Vec<Vec<(String, String)>> = vec![];
+    for attribute in attributes {
+        let next_contract = attribute.0 == CONTRACT_ADDRESS_KEY
+            && groups.last().map_or(false, |group| {
+                group.iter().any(|(key, _)| key == CONTRACT_ADDRESS_KEY)
+            });
+        match groups.last_mut() {
+            Some(group) if !next_contract => group.push(attribute),
+            _ => groups.push(vec![attribute]),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|attributes| TxEvent::new(type_str, attributes))
+        .collect()
+}
+
+impl TxEvents {
+    pub fn new(events: Vec<TxEvent>) -> Self {
+        let mut tx_events = TxEvents::default();
+        for event in events {
+            tx_events.push(event);
+        }
+        tx_events
+    }
+
+    /// Events as returned by the RPC `tx` and `block_results` endpoints.
+    pub fn from_abci(events: &[Event]) -> Self {
+        TxEvents::new(
+            events
+                .iter()
+                .map(|event| {
+                    let attributes = event
+                        .attributes
+                        .iter()
+                        .map(|tag| (tag.key.to_string(), tag.value.to_string()))
+                        .collect();
+                    TxEvent::new(&event.type_str, attributes)
+                })
+                .collect(),
+        )
+    }
+
+    /// Best-effort events for an already flattened map, such as a websocket
+    /// subscription delivers. Event boundaries are lost in the map, so the
+    /// nth value of each of a type's keys is taken to belong to its nth
+    /// event.
+    pub fn from_event_map(event_map: &EventMap) -> Self {
+        let mut events: Vec<TxEvent> = vec![];
+        let mut last_type = None;
+        let mut first_of_type = 0;
+        for (composite_key, values) in event_map {
+            let (type_str, key) = match composite_key.split_once('.') {
+                Some(split) => split,
+                None => continue,
+            };
+            if last_type != Some(type_str) {
+                last_type = Some(type_str);
+                first_of_type = events.len();
+            }
+            for (i, value) in values.iter().enumerate() {
+                if events.len() <= first_of_type + i {
+                    events.push(TxEvent::new(type_str, vec![]));
+                }
+                let event = &mut events[first_of_type + i];
+                event.attributes.push((key.to_string(), value.clone()));
+                if key == CONTRACT_ADDRESS_KEY && event.contract_address.is_none() {
+                    event.contract_address = Some(value.clone());
+                }
+            }
+        }
+        TxEvents::new(events)
+    }
+
+    /// Reads the events each message emitted from a transaction's ABCI log,
+    /// in message order. Returns nothing if the log isn't a list of message
+    /// logs, as for failed transactions.
+    pub fn from_message_logs(log: &str) -> Vec<TxEvents> {
+        let message_logs: Vec<MessageLog> = match serde_json::from_str(log) {
+            Ok(message_logs) => message_logs,
+            Err(_) => return vec![],
+        };
+        let message_count = message_logs
+            .iter()
+            .map(|message_log| message_log.msg_index + 1)
+            .max()
+            .unwrap_or_default();
+        let mut message_events = vec![TxEvents::default(); message_count];
+        for message_log in message_logs {
+            for event in message_log.events {
+                let attributes = event
+                    .attributes
+                    .into_iter()
+                    .map(|attribute| (attribute.key, attribute.value.unwrap_or_default()))
+                    .collect();
+                for event in split_by_contract(&event.type_str, attributes) {
+                    message_events[message_log.msg_index].push(event);
+                }
+            }
+        }
+        message_events
+    }
+
+    pub fn push(&mut self, event: TxEvent) {
+        for (key, value) in event.attributes.iter() {
+            self.event_map
+                .entry(format!("{}.{}", event.type_str, key))
+                .or_default()
+                .push(value.clone());
+        }
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[TxEvent] {
+        &self.events
+    }
+
+    /// Whether these events may include other messages' as well as those
+    /// of the message they are for, because they couldn't be told apart.
+    pub fn shared(&self) -> bool {
+        self.shared
+    }
+
+    /// The flattened view of the events.
+    pub fn event_map(&self) -> &EventMap {
+        &self.event_map
+    }
+
+    /// The events of type `type_str`, in order.
+    pub fn of_type<'a>(&'a self, type_str: &'a str) -> impl Iterator<Item = &'a TxEvent> + 'a {
+        self.events
+            .iter()
+            .filter(move |event| event.type_str == type_str)
+    }
+
+    /// The events of type `type_str`, in order, with the ones a message log
+    /// merged split back up: a record ends where one of its attributes
+    /// repeats.
+    pub fn records(&self, type_str: &str) -> Vec<TxEvent> {
+        let mut records = vec![];
+        for event in self.of_type(type_str) {
+            let mut groups: Vec<Vec<(String, String)>> = vec![];
+            for attribute in &event.attributes {
+                match groups.last_mut() {
+                    Some(group) if !group.iter().any(|(key, _)| *key == attribute.0) => {
+                        group.push(attribute.clone())
+                    }
+                    _ => groups.push(vec![attribute.clone()]),
+                }
+            }
+            records.extend(
+                groups
+                    .into_iter()
+                    .map(|attributes| TxEvent::new(type_str, attributes)),
+            );
+        }
+        records
+    }
+
+    /// The wasm events emitted by `contract_address`, in order.
+    pub fn wasm_events_from<'a>(
+        &'a self,
+        contract_address: &'a str,
+    ) -> impl Iterator<Item = &'a TxEvent> + 'a {
+        self.of_type("wasm")
+            .filter(move |event| event.contract_address.as_deref() == Some(contract_address))
+    }
+
+    /// The first event of type `type_str`.
+    pub fn first_event(&self, type_str: &str) -> anyhow::Result<&TxEvent> {
+        self.of_type(type_str)
+            .next()
+            .ok_or_else(|| anyhow!("no {} event", type_str))
+    }
+
+    /// The value of `key` in the first event of type `type_str` that has it.
+    pub fn first_attribute(&self, type_str: &str, key: &str) -> anyhow::Result<&str> {
+        self.of_type(type_str)
+            .find_map(|event| event.attribute(key))
+            .ok_or_else(|| anyhow!("no {} in any {} event", key, type_str))
+    }
+
+    /// The events message `msg_index` of a transaction of `message_count`
+    /// messages is indexed with: the events that message emitted, or all of
+    /// the transaction's events when they couldn't be told apart, along with
+    /// the transaction-level `tx` events (height, hash, result code) and the
+    /// message index.
+    pub fn for_message(
+        &self,
+        message_events: &[TxEvents],
+        msg_index: usize,
+        message_count: usize,
+    ) -> TxEvents {
+        let mut events = match message_events.get(msg_index) {
+            Some(message_events) => {
+                let mut events = message_events.clone();
+                for event in self.of_type("tx") {
+                    events.push(event.clone());
+                }
+                events
+            }
+            None => TxEvents {
+                shared: message_count > 1,
+                ..self.clone()
+            },
+        };
+        // Read back as MSG_INDEX_KEY.
+        events.push(TxEvent::new(
+            "tx",
+            vec![("msg_index".to_string(), msg_index.to_string())],
+        ));
+        events
+    }
+
+    /// The events of each message of a transaction, split out of its
+    /// ordered events at the `message` event with an `action` that the
+    /// chain emits ahead of each message's own events. Events before the
+    /// first message, such as the fee being paid, belong to none of them.
+    pub fn split_by_action(&self) -> Vec<TxEvents> {
+        let mut messages: Vec<TxEvents> = vec![];
+        for event in self.events.iter().filter(|event| event.type_str != "tx") {
+            if event.type_str == "message" && event.attribute("action").is_some() {
+                messages.push(TxEvents::default());
+            }
+            if let Some(message) = messages.last_mut() {
+                message.push(event.clone());
+            }
+        }
+        messages
+    }
+
+    /// The events of each of the `count` messages an authz `MsgExec`
+    /// dispatched, split out of the exec's events, or None if they can't be
+    /// told apart.
+    ///
+    /// Newer chains tag each of those events with the message's position.
+    /// Otherwise the events have to be in order, and are split at the
+    /// `message` event each module emits with a message's events: ahead of
+    /// them for wasm, after them for every other module.
+    pub fn split_wrapped(&self, count: usize) -> Option<Vec<TxEvents>> {
+        if self
+            .events
+            .iter()
+            .any(|event| event.attribute(AUTHZ_MSG_INDEX_KEY).is_some())
+        {
+            let mut messages = vec![TxEvents::default(); count];
+            for event in self.events.iter().filter(|event| event.type_str != "tx") {
+                // Logs merge the events of a type, each one's attributes
+                // ending with its message's position. Events of messages
+                // wrapped more than once end with every position, the
+                // outermost last.
+                let mut attributes = vec![];
+                for (i, (key, value)) in event.attributes.iter().enumerate() {
+                    let position_here = key == AUTHZ_MSG_INDEX_KEY
+                        && event
+                            .attributes
+                            .get(i + 1)
+                            .map_or(true, |(next_key, _)| next_key != AUTHZ_MSG_INDEX_KEY);
+                    if position_here {
+                        let position: usize = value.parse().ok()?;
+                        let event = TxEvent::new(&event.type_str, std::mem::take(&mut attributes));
+                        messages.get_mut(position)?.push(event);
+                    } else {
+                        attributes.push((key.clone(), value.clone()));
+                    }
+                }
+            }
+            return Some(messages);
+        }
+        let events = self
+            .events
+            .iter()
+            .filter(|event| event.type_str != "tx")
+            // The exec's own action.
+            .filter(|event| event.type_str != "message" || event.attribute("action").is_none());
+        if count == 1 {
+            let mut message = TxEvents::default();
+            for event in events {
+                message.push(event.clone());
+            }
+            return Some(vec![message]);
+        }
+        let mut messages: Vec<TxEvents> = vec![TxEvents::default()];
+        let mut opened = false;
+        for event in events {
+            let module = match event.type_str.as_str() {
+                "message" => event.attribute("module"),
+                _ => None,
+            };
+            if module == Some("wasm") {
+                if opened {
+                    messages.push(TxEvents::default());
+                }
+                opened = true;
+            }
+            let closes = module.is_some() && module != Some("wasm");
+            if closes && opened {
+                // Where the wasm message's events end is anyone's guess.
+                return None;
+            }
+            messages.last_mut()?.push(event.clone());
+            if closes {
+                messages.push(TxEvents::default());
+            }
+        }
+        if messages
+            .last()
+            .map_or(false, |message| message.events.is_empty())
+        {
+            messages.pop();
+        }
+        if messages.len() != count {
+            return None;
+        }
+        Some(messages)
+    }
+
+    /// The events a message wrapped in this one is indexed with: its own
+    /// `wrapped_events` if they could be split out, otherwise all of these,
+    /// along with these `tx` events and `msg_index` in place of this
+    /// message's index.
+    pub fn for_wrapped_message(
+        &self,
+        wrapped_events: Option<&TxEvents>,
+        msg_index: usize,
+    ) -> TxEvents {
+        let mut events = TxEvents {
+            shared: self.shared || wrapped_events.is_none(),
+            ..TxEvents::default()
+        };
+        for event in wrapped_events
+            .unwrap_or(self)
+            .events
+            .iter()
+            .filter(|event| event.type_str != "tx")
+        {
+            events.push(event.clone());
+        }
+        for event in self.of_type("tx") {
+            let attributes: Vec<(String, String)> = event
+                .attributes
+                .iter()
+                .filter(|(key, _)| key != "msg_index")
+                .cloned()
+                .collect();
+            if !attributes.is_empty() {
+                events.push(TxEvent::new("tx", attributes));
+            }
+        }
+        events.push(TxEvent::new(
+            "tx",
+            vec![("msg_index".to_string(), msg_index.to_string())],
+        ));
+        events
+    }
+}
+
+impl Deref for TxEvents {
+    type Target = EventMap;
+
+    fn deref(&self) -> &Self::Target {
+        &self.event_map
+    }
+}
+
+impl From<&TxEvents> for EventMap {
+    fn from(events: &TxEvents) -> Self {
+        events.event_map.clone()
+    }
+}
+
+#[test]
+fn test_tx_events() {
+    use crate::util::message_key::MSG_INDEX_KEY;
+    let log = r#"[
+        {"events":[
+            {"type":"message","attributes":[{"key":"action","value":"send"}]},
+            {"type":"transfer","attributes":[{"key":"amount","value":"10ujuno"}]}
+        ]},
+        {"msg_index":1,"events":[
+            {"type":"wasm","attributes":[
+                {"key":"_contract_address","value":"juno1token"},
+                {"key":"action","value":"send"},
+                {"key":"amount","value":"20"},
+                {"key":"_contract_address","value":"juno1staking"},
+                {"key":"action","value":"stake"},
+                {"key":"amount","value":"15"}
+            ]},
+            {"type":"wasm","attributes":[
+                {"key":"_contract_address","value":"juno1staking"},
+                {"key":"action","value":"stake"}
+            ]}
+        ]}
+    ]"#;
+    let message_events = TxEvents::from_message_logs(log);
+    assert_eq!(2, message_events.len());
+    assert_eq!(
+        vec!["10ujuno".to_string()],
+        message_events[0]["transfer.amount"]
+    );
+    let wasm = &message_events[1];
+    // The merged wasm event is split up by contract.
+    assert_eq!(
+        vec!["juno1token", "juno1staking", "juno1staking"],
+        wasm.of_type("wasm")
+            .map(|event| event.contract_address.as_deref().unwrap())
+            .collect::<Vec<&str>>()
+    );
+    assert_eq!(2, wasm.wasm_events_from("juno1staking").count());
+    assert_eq!("20", wasm.first_attribute("wasm", "amount").unwrap());
+    assert!(wasm.first_attribute("wasm", "recipient").is_err());
+    assert!(wasm.first_event("transfer").is_err());
+    let stake = wasm.wasm_events_from("juno1staking").next().unwrap();
+    assert_eq!("15", stake.require("amount").unwrap());
+    assert_eq!(vec!["20", "15"], wasm["wasm.amount"]);
+
+    let merged = TxEvents::new(vec![TxEvent::new(
+        "unbond",
+        vec![
+            ("validator".to_string(), "junovaloper1a".to_string()),
+            ("amount".to_string(), "10".to_string()),
+            ("validator".to_string(), "junovaloper1b".to_string()),
+            ("amount".to_string(), "20".to_string()),
+        ],
+    )]);
+    let unbonds = merged.records("unbond");
+    assert_eq!(2, unbonds.len());
+    assert_eq!(Some("junovaloper1b"), unbonds[1].attribute("validator"));
+    assert_eq!(Some("20"), unbonds[1].attribute("amount"));
+
+    let tx_events = TxEvents::new(vec![
+        TxEvent::new("tx", vec![("height".to_string(), "42".to_string())]),
+        TxEvent::new(
+            "transfer",
+            vec![("amount".to_string(), "10ujuno".to_string())],
+        ),
+    ]);
+    let second = tx_events.for_message(&message_events, 1, 2);
+    assert_eq!(vec!["42".to_string()], second["tx.height"]);
+    assert_eq!(vec!["1".to_string()], second[MSG_INDEX_KEY]);
+    assert!(second.get("transfer.amount").is_none());
+    assert!(!second.shared());
+
+    // Without a usable log every message sees the whole transaction.
+    assert!(TxEvents::from_message_logs("out of gas").is_empty());
+    let unscoped = tx_events.for_message(&[], 1, 2);
+    assert_eq!(vec!["10ujuno".to_string()], unscoped["transfer.amount"]);
+    assert!(unscoped.shared());
+    assert!(!tx_events.for_message(&[], 0, 1).shared());
+
+    // The adapter and a flattened map round trip.
+    let flattened = EventMap::from(wasm);
+    let rebuilt = TxEvents::from_event_map(&flattened);
+    assert_eq!(flattened, *rebuilt);
+    assert_eq!(
+        Some("juno1token"),
+        rebuilt
+            .first_event("wasm")
+            .unwrap()
+            .contract_address
+            .as_deref()
+    );
+}
+
+#[test]
+fn test_split_wrapped() {
+    let attributes = |attributes: &[(&str, &str)]| -> Vec<(String, String)> {
+        attributes
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    };
+    // Events merged in a log, tagged with their message's position.
+    let merged = TxEvents::new(vec![TxEvent::new(
+        "delegate",
+        attributes(&[
+            ("validator", "junovaloper1a"),
+            (AUTHZ_MSG_INDEX_KEY, "0"),
+            ("validator", "junovaloper1b"),
+            (AUTHZ_MSG_INDEX_KEY, "1"),
+        ]),
+    )]);
+    let split = merged.split_wrapped(2).unwrap();
+    assert_eq!(
+        vec!["junovaloper1b".to_string()],
+        split[1]["delegate.validator"]
+    );
+    assert!(merged.split_wrapped(1).is_none());
+
+    // Ordered events, split at the modules' message events.
+    let ordered = TxEvents::new(vec![
+        TxEvent::new(
+            "message",
+            attributes(&[("action", "/cosmos.authz.v1beta1.MsgExec")]),
+        ),
+        TxEvent::new("transfer", attributes(&[("amount", "5ujuno")])),
+        TxEvent::new("message", attributes(&[("module", "bank")])),
+        TxEvent::new("message", attributes(&[("module", "wasm")])),
+        TxEvent::new("wasm", attributes(&[("_contract_address", "juno1dao")])),
+    ]);
+    let split = ordered.split_wrapped(2).unwrap();
+    assert_eq!(vec!["5ujuno".to_string()], split[0]["transfer.amount"]);
+    assert_eq!(0, split[0].wasm_events_from("juno1dao").count());
+    assert_eq!(1, split[1].wasm_events_from("juno1dao").count());
+    assert!(split[1].get("transfer.amount").is_none());
+    assert!(ordered.split_wrapped(3).is_none());
+    assert_eq!(1, ordered.split_by_action().len());
+
+    // A wasm message's events run up to the next message's, so the next
+    // message's can't be told from them unless it's wasm too.
+    let wasm_first = TxEvents::new(vec![
+        TxEvent::new("message", attributes(&[("module", "wasm")])),
+        TxEvent::new("wasm", attributes(&[("_contract_address", "juno1dao")])),
+        TxEvent::new("transfer", attributes(&[("amount", "5ujuno")])),
+        TxEvent::new("message", attributes(&[("module", "bank")])),
+    ]);
+    assert!(wasm_first.split_wrapped(2).is_none());
+}
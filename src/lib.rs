@@ -14,13 +14,29 @@ extern crate diesel;
 extern crate dotenvy;
 extern crate env_logger;
 
+/// Fetch and parse whole blocks, for nodes without a tx indexer
+pub mod block_parser;
 /// Configure run-time indexing behavior
 pub mod config;
 /// Infrastructure for interacting with SQL databases
 pub mod db;
+/// Liveness and readiness checks
+pub mod health;
 /// Fetch and parse messages already committed to the blockchain
 pub mod historical_parser;
 /// Core indexing infrastructure
 pub mod indexing;
+/// Index new transactions from a live websocket subscription
+pub mod listener;
+/// Prometheus metrics for indexer throughput and health
+pub mod metrics;
+/// Clients for the Tendermint RPC nodes we index from
+pub mod rpc;
+/// HTTP endpoints for metrics and health checks
+pub mod server;
+/// Stop long-running work cleanly on SIGINT/SIGTERM
+pub mod shutdown;
+/// Where transactions to index come from: RPC, websocket, files or the database
+pub mod source;
 /// Disorganized grab bag of utility functions used across the project.
 pub mod util;
@@ -0,0 +1,272 @@
+use crate::config::IndexerConfig;
+use crate::health::{record_progress, HealthChecks};
+use crate::historical_parser::{sync_block_range, sync_to_chain_head};
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::msg_set::MsgSet;
+use crate::metrics::{record_indexed_height, record_live_height};
+use crate::shutdown::ShutdownSignal;
+use crate::source::websocket_source::WebSocketSource;
+use crate::source::{index_tx, SourceTx, TxSource};
+use crate::util::height_ranges::HeightRange;
+use crate::util::query_stream::backoff_delay;
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+// How many heights of transaction hashes are remembered for de-duplication.
+const SEEN_HEIGHTS: u64 = 10;
+
+// How often the listener checks the chain can be reached, and so reports it
+// is alive, while no transactions arrive.
+const IDLE_HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Decides which transactions from the live subscription still need
+/// indexing: those above the height history was indexed through, and not
+/// already delivered by the subscription.
+#[derive(Debug, Default)]
+pub struct LiveFilter {
+    indexed_through: u64,
+    last_height: u64,
+    seen: BTreeMap<u64, HashSet<String>>,
+}
+
+impl LiveFilter {
+    pub fn new(indexed_through: u64) -> Self {
+        LiveFilter {
+            indexed_through,
+            last_height: 0,
+            seen: BTreeMap::new(),
+        }
+    }
+
+    pub fn indexed_through(&self) -> u64 {
+        self.indexed_through
+    }
+
+    /// Records that every block up to `height` has been indexed.
+    pub fn indexed_up_to(&mut self, height: u64) {
+        self.indexed_through = self.indexed_through.max(height);
+    }
+
+    /// Where to resume after the subscription dropped: the height of the
+    /// last live transaction, since the connection may have dropped part-way
+    /// through its block, or else the block after the one history was
+    /// indexed through. None if nothing has been indexed yet.
+    pub fn resume_height(&self) -> Option<u64> {
+        match (self.last_height, self.indexed_through) {
+            (0, 0) => None,
+            (last_height, indexed_through) => Some(last_height.max(indexed_through + 1)),
+        }
+    }
+
+    /// Returns true if the transaction `tx_hash` at `height` should be
+    /// indexed, remembering it so a repeat delivery is skipped.
+    pub fn accept(&mut self, height: u64, tx_hash: Option<&str>) -> bool {
+        if height <= self.indexed_through {
+            return false;
+        }
+        if let Some(tx_hash) = tx_hash {
+            if !self
+                .seen
+                .entry(height)
+                .or_default()
+                .insert(tx_hash.to_string())
+            {
+                return false;
+            }
+        }
+        self.last_height = self.last_height.max(height);
+        self.seen = self.seen.split_off(&height.saturating_sub(SEEN_HEIGHTS));
+        true
+    }
+}
+
+// Opens a new subscription, retrying with backoff until it succeeds.
+// Returns None if shutdown is requested first.
+async fn reconnect(config: &IndexerConfig, shutdown: &ShutdownSignal) -> Option<WebSocketSource> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let delay = backoff_delay(
+            config.reconnect_sleep,
+            config.max_reconnect_sleep,
+            attempt,
+            rand::random(),
+        );
+        info!("Reconnecting websocket in {:?}", delay);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.wait() => return None,
+        }
+        match WebSocketSource::start(config).await {
+            Ok(subscription) => return Some(subscription),
+            Err(e) => warn!("Reconnect attempt {} failed: {:?}", attempt, e),
+        }
+    }
+}
+
+// Indexes one transaction from the subscription. Before the first one
+// when `fill_gap` is set, the blocks between history and that transaction
+// are indexed over RPC.
+async fn index_live_tx(
+    source_tx: &SourceTx,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    filter: &mut LiveFilter,
+    fill_gap: &mut bool,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<()> {
+    let height = source_tx.height;
+    if std::mem::take(fill_gap) && height > filter.indexed_through() + 1 {
+        let gap = HeightRange::new(filter.indexed_through() + 1, height - 1);
+        info!("indexing blocks {} before live transactions", gap);
+        let failed = sync_block_range(registry, config, msg_set.clone(), gap, shutdown).await?;
+        if !failed.is_empty() {
+            warn!("blocks left for backfill: {:?}", failed);
+        }
+        filter.indexed_up_to(height - 1);
+    }
+    if !filter.accept(height, Some(source_tx.hash.as_str())) {
+        debug!(
+            "skipping already indexed tx {} at {}",
+            source_tx.hash, height
+        );
+        return Ok(());
+    }
+    index_tx(source_tx, registry, config, msg_set)?;
+    record_live_height(height);
+    Ok(())
+}
+
+/// Indexes transactions from `subscription` for as long as the indexer runs.
+///
+/// `indexed_through` is the height history has been indexed up to, if any.
+/// Buffered transactions at or below it are skipped, and if the first live
+/// transaction is further ahead the blocks in between are fetched over RPC
+/// first, so there is no gap between history and live indexing.
+///
+/// When the subscription drops it is reopened with backoff, and the blocks
+/// produced in the meantime are indexed over RPC from the last indexed
+/// height before live indexing resumes. Events that can't be indexed are
+/// logged and skipped.
+///
+/// Returns once `shutdown` is requested, after the transaction being indexed
+/// is finished and the websocket is closed.
+pub async fn listen(
+    mut subscription: WebSocketSource,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    indexed_through: Option<u64>,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<()> {
+    let mut filter = LiveFilter::new(indexed_through.unwrap_or_default());
+    let mut fill_gap = indexed_through.is_some();
+    let checks = HealthChecks::from_config(config);
+    let mut idle = tokio::time::interval(IDLE_HEARTBEAT);
+    loop {
+        loop {
+            let next_batch = tokio::select! {
+                next_batch = subscription.next_batch() => next_batch,
+                _ = idle.tick() => {
+                    // Waiting on a quiet chain still counts as progress, as
+                    // long as the chain can be reached at all. Once history
+                    // is joined up with the subscription, a quiet chain is
+                    // indexed through its head.
+                    match checks.chain_head().await {
+                        Ok(chain_head) => {
+                            record_progress();
+                            if !fill_gap {
+                                record_indexed_height(chain_head);
+                            }
+                        }
+                        Err(e) => warn!("Live indexer cannot reach the chain: {:?}", e),
+                    }
+                    continue;
+                }
+                _ = shutdown.wait() => Ok(None),
+            };
+            let batch = match next_batch {
+                Ok(Some(batch)) => batch,
+                Ok(None) => break,
+                Err(e) => {
+                    error!("Error reading live transactions: {:?}", e);
+                    continue;
+                }
+            };
+            for source_tx in batch.txs.iter() {
+                if let Err(e) = index_live_tx(
+                    source_tx,
+                    registry,
+                    config,
+                    msg_set.clone(),
+                    &mut filter,
+                    &mut fill_gap,
+                    shutdown,
+                )
+                .await
+                {
+                    error!("Failed indexing live tx {}: {:?}", source_tx.hash, e);
+                }
+            }
+        }
+        subscription.close().await;
+        if shutdown.is_shutdown() {
+            return Ok(());
+        }
+        warn!("Websocket subscription ended");
+        subscription = match reconnect(config, shutdown).await {
+            Some(subscription) => subscription,
+            None => return Ok(()),
+        };
+        if let Some(resume_height) = filter.resume_height() {
+            info!("catching up from block {}", resume_height);
+            let caught_up =
+                sync_to_chain_head(registry, config, msg_set.clone(), resume_height, shutdown)
+                    .await;
+            match caught_up {
+                Ok((chain_head, failed)) => {
+                    if !failed.is_empty() {
+                        warn!("blocks left for backfill: {:?}", failed);
+                    }
+                    filter.indexed_up_to(chain_head);
+                    fill_gap = false;
+                }
+                Err(e) => error!(
+                    "Failed catching up from block {}, missed blocks are left for backfill: {:?}",
+                    resume_height, e
+                ),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_live_filter() {
+    let mut filter = LiveFilter::new(100);
+    // Heights the historical sync already covered are skipped.
+    assert!(!filter.accept(99, Some("A")));
+    assert!(!filter.accept(100, Some("B")));
+    assert!(filter.accept(101, Some("C")));
+    // So are repeat deliveries.
+    assert!(!filter.accept(101, Some("C")));
+    assert!(filter.accept(101, Some("D")));
+    assert!(filter.accept(102, None));
+    assert!(filter.accept(102, None));
+
+    filter.indexed_up_to(150);
+    assert_eq!(150, filter.indexed_through());
+    assert!(!filter.accept(120, Some("E")));
+    filter.indexed_up_to(140);
+    assert_eq!(150, filter.indexed_through());
+
+    assert_eq!(Some(151), filter.resume_height());
+    assert!(filter.accept(180, Some("G")));
+    assert_eq!(Some(180), filter.resume_height());
+    assert_eq!(None, LiveFilter::new(0).resume_height());
+
+    // Old hashes are forgotten.
+    assert!(filter.accept(200, Some("F")));
+    assert!(filter.seen.keys().all(|height| *height >= 190));
+}
@@ -6,7 +6,8 @@ use dao_indexer::config::IndexerConfig;
 use dao_indexer::db::connection::establish_connection;
 use dao_indexer::db::db_persister::DatabasePersister;
 use dao_indexer::db::persister::{make_persister_ref, Persister, PersisterRef, StubPersister};
-use dao_indexer::historical_parser::block_synchronizer;
+use dao_indexer::health::HealthChecks;
+use dao_indexer::historical_parser::{backfill_synchronizer, block_synchronizer};
 use dao_indexer::indexing::indexer_registry::{IndexerRegistry, Register};
 use dao_indexer::indexing::indexers::msg_cw20_indexer::Cw20ExecuteMsgIndexer;
 use dao_indexer::indexing::indexers::msg_cw3dao_indexer::{
@@ -15,6 +16,12 @@ use dao_indexer::indexing::indexers::msg_cw3dao_indexer::{
 use dao_indexer::indexing::indexers::msg_cw3multisig_indexer::{
     Cw3MultisigExecuteMsgIndexer, Cw3MultisigInstantiateMsgIndexer,
 };
+use dao_indexer::listener::listen;
+use dao_indexer::server::serve;
+use dao_indexer::shutdown::ShutdownSignal;
+use dao_indexer::source::archive_source::ArchiveSource;
+use dao_indexer::source::index_source;
+use dao_indexer::source::websocket_source::WebSocketSource;
 
 use cw3_multisig::msg::ExecuteMsg as Cw3MultisigExecuteMsg25;
 use cw3_multisig::msg::InstantiateMsg as Cw3MultisigInstantiateMsg25;
@@ -24,19 +31,16 @@ use stake_cw20::msg::ExecuteMsg as StakeCw20ExecuteMsg25;
 use dao_indexer::indexing::indexers::msg_stake_cw20_indexer::StakeCw20ExecuteMsgIndexer;
 use dao_indexer::indexing::msg_set::default_msg_set;
 use dao_indexer::indexing::schema_indexer::{SchemaIndexer, SchemaRef};
-use dao_indexer::indexing::tx::process_tx_info;
 use diesel::pg::PgConnection;
 use env_logger::Env;
-use futures::StreamExt;
-use log::{debug, error, info, warn};
-use tendermint_rpc::event::EventData;
-use tendermint_rpc::query::EventType;
-use tendermint_rpc::{SubscriptionClient, WebSocketClient};
+use log::{error, info, warn};
 
 use cw3_dao::msg::ExecuteMsg as Cw3DaoExecuteMsg_030;
 use cw3_dao::msg::InstantiateMsg as Cw3DaoInstantiateMsg_030;
 use schemars::schema_for;
 use sea_orm::{Database, DatabaseConnection};
+use std::net::SocketAddr;
+use std::path::Path;
 
 /// This indexes the Tendermint blockchain starting from a specified block, then
 /// listens for new blocks and indexes them with content-aware indexers.
@@ -180,6 +184,7 @@ async fn main() -> anyhow::Result<()> {
         registry.register(Box::from(cw3dao_indexer), None);
         registry.register(Box::from(cw20_stake_indexer), None);
     }
+    registry.set_failed_tx_indexers(&config.failed_tx_indexers);
     registry.initialize()?;
 
     if let Some(seaql_db) = &registry.seaql_db {
@@ -189,9 +194,55 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let msg_set = default_msg_set();
+    let shutdown = ShutdownSignal::from_os_signals();
 
+    if !config.http_listen_address.is_empty() {
+        let address: SocketAddr = config.http_listen_address.parse()?;
+        let checks = HealthChecks::from_config(&config);
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve(address, checks, shutdown).await {
+                error!("HTTP server failed: {:?}", e);
+            }
+        });
+    }
+
+    if !config.archive_replay_dir.is_empty() {
+        let mut source = ArchiveSource::open(
+            Path::new(&config.archive_replay_dir),
+            registry.db.as_ref(),
+            config.tendermint_save_all_blocks,
+        )?;
+        let indexed =
+            index_source(&mut source, &registry, &config, msg_set.clone(), &shutdown).await?;
+        info!(
+            "Replayed {} transactions from {}",
+            indexed, config.archive_replay_dir
+        );
+        return Ok(());
+    }
+
+    // Subscribe before syncing history so that blocks produced during the
+    // sync are buffered rather than missed.
+    let subscription = if config.listen {
+        Some(WebSocketSource::start(&config).await?)
+    } else {
+        None
+    };
+
+    let mut indexed_through = None;
     if config.enable_indexer_env {
-        block_synchronizer(&registry, &config, msg_set.clone()).await?;
+        if config.backfill {
+            let report =
+                backfill_synchronizer(&registry, &config, msg_set.clone(), &shutdown).await?;
+            if !report.unfilled.is_empty() {
+                warn!("{}", report);
+            }
+            indexed_through = Some(report.last_height);
+        } else {
+            indexed_through =
+                Some(block_synchronizer(&registry, &config, msg_set.clone(), &shutdown).await?);
+        }
         if let Ok(msg_set) = msg_set.lock() {
             if !msg_set.unregistered_msgs.is_empty() {
                 warn!(
@@ -204,36 +255,25 @@ async fn main() -> anyhow::Result<()> {
         info!("Indexing historical blocks disabled");
     }
 
-    if config.listen {
-        let (client, driver) =
-            WebSocketClient::new::<&str>(&config.tendermint_websocket_url).await?;
-        let driver_handle = tokio::spawn(async move { driver.run().await });
-
-        // Subscribe to transactions (can also add blocks but just Tx for now)
-        let mut subs = client.subscribe(EventType::Tx.into()).await?;
-
-        while let Some(res) = subs.next().await {
-            let ev = res?;
-            let result = ev.data;
-            let events = ev.events.unwrap();
-            match result {
-                EventData::NewBlock { block, .. } => debug!("{:?}", block.unwrap()),
-                EventData::Tx { tx_result, .. } => {
-                    process_tx_info(&registry, tx_result, &events, msg_set.clone())?
-                }
-                _ => {
-                    error!("Unexpected result {:?}", result)
-                }
-            }
-        }
-        // Signal to the driver to terminate.
-        match client.close() {
-            Ok(val) => info!("closed {:?}", val),
-            Err(e) => error!("Error closing client {:?}", e),
+    if let Some(subscription) = subscription {
+        if shutdown.is_shutdown() {
+            subscription.close().await;
+        } else {
+            listen(
+                subscription,
+                &registry,
+                &config,
+                msg_set.clone(),
+                indexed_through,
+                &shutdown,
+            )
+            .await?;
         }
-        // Await the driver's termination to ensure proper connection closure.
-        let _ = driver_handle.await.unwrap();
     }
 
+    // Dropping the registry and persister closes their database connections.
+    drop(registry);
+    drop(persister_ref);
+    info!("Indexer stopped");
     Ok(())
 }
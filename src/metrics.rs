@@ -0,0 +1,154 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Throughput is exported as counters; Prometheus' rate() turns them into
+// blocks, transactions and messages per second.
+
+/// The highest height indexed so far.
+pub static CURRENT_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("indexer_current_height", "Highest block height indexed").unwrap()
+});
+
+/// The height being synced to: the final block or the chain head.
+pub static TARGET_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "indexer_target_height",
+        "Block height the indexer is syncing to"
+    )
+    .unwrap()
+});
+
+pub static BLOCKS_PROCESSED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("indexer_blocks_processed_total", "Blocks indexed").unwrap()
+});
+
+pub static TXS_PROCESSED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("indexer_txs_processed_total", "Transactions indexed").unwrap()
+});
+
+pub static MESSAGES_PROCESSED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "indexer_messages_processed_total",
+        "Transaction messages processed"
+    )
+    .unwrap()
+});
+
+pub static RPC_REQUEST_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "indexer_rpc_request_seconds",
+        "Latency of RPC requests by endpoint and method",
+        &["endpoint", "method"]
+    )
+    .unwrap()
+});
+
+pub static RPC_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_rpc_errors_total",
+        "Failed RPC requests by endpoint and method",
+        &["endpoint", "method"]
+    )
+    .unwrap()
+});
+
+/// Requests put back on a `QueryStream` to be tried again.
+pub static QUERY_REQUEUES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "indexer_query_requeues_total",
+        "tx_search requests requeued for another attempt"
+    )
+    .unwrap()
+});
+
+/// Messages each registered indexer handled, by result ("success" or
+/// "failure").
+pub static INDEXER_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_messages_indexed_total",
+        "Messages handled by each indexer",
+        &["indexer", "result"]
+    )
+    .unwrap()
+});
+
+pub static UNREGISTERED_MSGS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "indexer_unregistered_msgs",
+        "Distinct message types seen with no handler"
+    )
+    .unwrap()
+});
+
+// Backs CURRENT_HEIGHT, so concurrent syncs can only ever move it up.
+static INDEXED_HEIGHT: AtomicU64 = AtomicU64::new(0);
+
+/// Records that the blocks up to `height` have been indexed. The current
+/// height never moves back, so backfilling older blocks leaves it alone.
+pub fn record_indexed_height(height: u64) {
+    let previous = INDEXED_HEIGHT.fetch_max(height, Ordering::Relaxed);
+    CURRENT_HEIGHT.set(previous.max(height) as i64);
+}
+
+/// The height every block up to has been indexed, as far as is known.
+pub fn indexed_height() -> u64 {
+    INDEXED_HEIGHT.load(Ordering::Relaxed)
+}
+
+/// Records indexing a live block at `height`. Live blocks are the chain
+/// head, so the target height follows them.
+pub fn record_live_height(height: u64) {
+    record_indexed_height(height);
+    let height = height as i64;
+    if TARGET_HEIGHT.get() < height {
+        TARGET_HEIGHT.set(height);
+    }
+}
+
+/// Runs an RPC request to `endpoint`, recording its latency and whether it
+/// failed.
+pub async fn time_rpc<T, E, F>(endpoint: &str, method: &str, request: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let timer = RPC_REQUEST_SECONDS
+        .with_label_values(&[endpoint, method])
+        .start_timer();
+    let result = request.await;
+    timer.observe_duration();
+    if result.is_err() {
+        RPC_ERRORS.with_label_values(&[endpoint, method]).inc();
+    }
+    result
+}
+
+/// Every registered metric in the Prometheus text format.
+pub fn encode_metrics() -> anyhow::Result<String> {
+    let mut buffer = vec![];
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+#[tokio::test]
+async fn test_time_rpc() {
+    let errors = || {
+        RPC_ERRORS
+            .with_label_values(&["http://test:26657", "status"])
+            .get()
+    };
+    let result: Result<u32, &str> = time_rpc("http://test:26657", "status", async { Ok(1) }).await;
+    assert_eq!(Ok(1), result);
+    assert_eq!(0, errors());
+    let result: Result<u32, &str> =
+        time_rpc("http://test:26657", "status", async { Err("refused") }).await;
+    assert!(result.is_err());
+    assert_eq!(1, errors());
+    assert!(encode_metrics().unwrap().contains(
+        "indexer_rpc_request_seconds_count{endpoint=\"http://test:26657\",method=\"status\"} 2"
+    ));
+}
@@ -0,0 +1,3 @@
+pub mod archive;
+pub mod client_pool;
+pub mod rate_limiter;
@@ -0,0 +1,165 @@
+use crate::util::height_ranges::HeightRange;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tendermint_rpc::endpoint::block::Response as BlockResponse;
+use tendermint_rpc::endpoint::block_results::Response as BlockResultsResponse;
+use tendermint_rpc::endpoint::tx_search::Response as TxSearchResponse;
+
+const ARCHIVE_FILE_SUFFIX: &str = ".ndjson.gz";
+
+/// One RPC response as written to an archive file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    TxSearch {
+        query: String,
+        page: u32,
+        response: TxSearchResponse,
+    },
+    Block {
+        response: BlockResponse,
+    },
+    BlockResults {
+        response: BlockResultsResponse,
+    },
+}
+
+/// The archive file holding records for `height`: the range of
+/// `blocks_per_file` blocks containing it.
+pub fn archive_file_range(height: u64, blocks_per_file: u64) -> HeightRange {
+    let blocks_per_file = blocks_per_file.max(1);
+    let start = height - height % blocks_per_file;
+    HeightRange::new(start, start + blocks_per_file - 1)
+}
+
+pub fn archive_file_name(range: &HeightRange) -> String {
+    format!(
+        "{:012}-{:012}{}",
+        range.start, range.end, ARCHIVE_FILE_SUFFIX
+    )
+}
+
+/// The height range in an archive file's name, or None if `file_name`
+/// isn't an archive file.
+pub fn parse_archive_file_name(file_name: &str) -> Option<HeightRange> {
+    let (start, end) = file_name
+        .strip_suffix(ARCHIVE_FILE_SUFFIX)?
+        .split_once('-')?;
+    Some(HeightRange::new(start.parse().ok()?, end.parse().ok()?))
+}
+
+/// The archive files in `dir`, in height order.
+pub fn archive_files(dir: &Path) -> anyhow::Result<Vec<(HeightRange, PathBuf)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let range = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_archive_file_name);
+        if let Some(range) = range {
+            files.push((range, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads every record in an archive file.
+pub fn read_archive_file(path: &Path) -> anyhow::Result<Vec<ArchiveRecord>> {
+    let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut records = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+}
+
+/// Writes RPC responses to gzipped, newline-delimited JSON files in a
+/// directory, one file per `blocks_per_file` blocks, so they can be replayed
+/// later without a node.
+///
+/// Every record is appended as its own gzip member, so a file stays
+/// readable if the indexer stops part-way through writing it.
+#[derive(Debug)]
+pub struct ArchiveRecorder {
+    dir: PathBuf,
+    blocks_per_file: u64,
+    lock: Mutex<()>,
+}
+
+impl ArchiveRecorder {
+    pub fn new(dir: &Path, blocks_per_file: u64) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(ArchiveRecorder {
+            dir: dir.to_path_buf(),
+            blocks_per_file: blocks_per_file.max(1),
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Appends `record` to the file for `height`.
+    pub fn record(&self, height: u64, record: &ArchiveRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&line)?;
+        let compressed = encoder.finish()?;
+
+        let range = archive_file_range(height, self.blocks_per_file);
+        let path = self.dir.join(archive_file_name(&range));
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(&compressed)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_archive_round_trip() {
+    let dir = std::env::temp_dir().join(format!("dao_indexer_archive_{}", std::process::id()));
+    let recorder = ArchiveRecorder::new(&dir, 1000).unwrap();
+    let record = |height: u64| ArchiveRecord::TxSearch {
+        query: format!("tx.height >= {}", height),
+        page: 1,
+        response: TxSearchResponse {
+            txs: vec![crate::source::test_response(height, 0)],
+            total_count: 1,
+        },
+    };
+    recorder.record(2500, &record(2500)).unwrap();
+    recorder.record(10, &record(10)).unwrap();
+    recorder.record(20, &record(20)).unwrap();
+
+    let files = archive_files(&dir).unwrap();
+    let ranges: Vec<HeightRange> = files.iter().map(|(range, _)| *range).collect();
+    assert_eq!(
+        vec![HeightRange::new(0, 999), HeightRange::new(2000, 2999)],
+        ranges
+    );
+    assert_eq!(
+        "000000002000-000000002999.ndjson.gz",
+        archive_file_name(&ranges[1])
+    );
+    let records = read_archive_file(&files[0].1).unwrap();
+    assert_eq!(2, records.len());
+    match &records[1] {
+        ArchiveRecord::TxSearch { page, response, .. } => {
+            assert_eq!(1, *page);
+            assert_eq!(20, response.txs[0].height.value());
+        }
+        other => panic!("unexpected record {:?}", other),
+    }
+    assert_eq!(None, parse_archive_file_name("notes.txt"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,404 @@
+use super::archive::{ArchiveRecord, ArchiveRecorder};
+use super::rate_limiter::{is_throttling_error, RateLimiter};
+use crate::config::IndexerConfig;
+use crate::health::record_progress;
+use crate::metrics::time_rpc;
+use log::{debug, info, warn};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tendermint::block::Height;
+use tendermint_rpc::endpoint::block::Response as BlockResponse;
+use tendermint_rpc::endpoint::block_results::Response as BlockResultsResponse;
+use tendermint_rpc::endpoint::tx_search::Response as TxSearchResponse;
+use tendermint_rpc::query::Query;
+use tendermint_rpc::{Client, HttpClient, Order};
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    healthy: bool,
+    consecutive_errors: u32,
+    evicted_until: Option<Instant>,
+    latest_block_height: u64,
+}
+
+/// A single RPC node and what we currently know about it.
+#[derive(Debug)]
+pub struct RpcEndpoint {
+    pub url: String,
+    client: HttpClient,
+    limiter: RateLimiter,
+    state: Mutex<EndpointState>,
+}
+
+impl RpcEndpoint {
+    pub fn new(url: &str, limiter: RateLimiter) -> anyhow::Result<Self> {
+        let client = HttpClient::new::<&str>(url)?;
+        Ok(RpcEndpoint {
+            url: url.to_string(),
+            client,
+            limiter,
+            state: Mutex::new(EndpointState::default()),
+        })
+    }
+
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    pub fn latest_block_height(&self) -> u64 {
+        self.state.lock().unwrap().latest_block_height
+    }
+
+    // Healthy, not evicted, and far enough along to serve `min_height`.
+    fn is_available(&self, min_height: u64, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if let Some(evicted_until) = state.evicted_until {
+            if evicted_until > now {
+                return false;
+            }
+            info!("Re-admitting RPC endpoint {}", self.url);
+            state.evicted_until = None;
+        }
+        state.healthy && state.latest_block_height >= min_height
+    }
+
+    fn record_status(&self, latest_block_height: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.healthy = true;
+        state.consecutive_errors = 0;
+        state.latest_block_height = latest_block_height;
+    }
+
+    fn record_success(&self) {
+        self.state.lock().unwrap().consecutive_errors = 0;
+    }
+
+    // Returns true if this error got the endpoint evicted.
+    fn record_error(&self, max_errors: u32, eviction: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_errors += 1;
+        if state.consecutive_errors < max_errors {
+            return false;
+        }
+        state.consecutive_errors = 0;
+        state.evicted_until = Some(Instant::now() + eviction);
+        true
+    }
+}
+
+/// Spreads RPC requests across a set of Tendermint nodes, skipping nodes
+/// that are unhealthy, have been evicted after repeated errors, or haven't
+/// reached the heights being requested yet.
+#[derive(Debug)]
+pub struct ClientPool {
+    endpoints: Vec<RpcEndpoint>,
+    next_endpoint: AtomicUsize,
+    max_errors: u32,
+    eviction: Duration,
+    recorder: Option<ArchiveRecorder>,
+}
+
+impl ClientPool {
+    pub fn new(
+        endpoints: Vec<RpcEndpoint>,
+        max_errors: u32,
+        eviction: Duration,
+    ) -> anyhow::Result<Self> {
+        if endpoints.is_empty() {
+            return Err(anyhow::anyhow!("No RPC endpoints configured"));
+        }
+        Ok(ClientPool {
+            endpoints,
+            next_endpoint: AtomicUsize::new(0),
+            max_errors: max_errors.max(1),
+            eviction,
+            recorder: None,
+        })
+    }
+
+    /// Writes every successful response to `recorder`.
+    pub fn with_recorder(mut self, recorder: ArchiveRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn from_config(config: &IndexerConfig) -> anyhow::Result<Self> {
+        let endpoints = config
+            .tendermint_rpc_urls
+            .iter()
+            .zip(config.rpc_rate_limits.iter())
+            .map(|(url, rate)| RpcEndpoint::new(url, RateLimiter::new(*rate, config.rpc_burst)))
+            .collect::<anyhow::Result<Vec<RpcEndpoint>>>()?;
+        let pool = ClientPool::new(
+            endpoints,
+            config.rpc_max_errors,
+            Duration::from_secs(config.rpc_eviction_seconds),
+        )?;
+        if config.archive_record_dir.is_empty() {
+            return Ok(pool);
+        }
+        info!("Recording RPC responses in {}", config.archive_record_dir);
+        let recorder = ArchiveRecorder::new(
+            Path::new(&config.archive_record_dir),
+            config.archive_blocks_per_file,
+        )?;
+        Ok(pool.with_recorder(recorder))
+    }
+
+    /// Builds the pool for `config` and waits for at least one endpoint to
+    /// become healthy.
+    pub async fn connect(config: &IndexerConfig) -> anyhow::Result<Self> {
+        let pool = ClientPool::from_config(config)?;
+        info!("Waiting for healthy RPC nodes...");
+        for endpoint in pool.endpoints.iter() {
+            if let Err(e) = endpoint
+                .client
+                .wait_until_healthy(Duration::from_millis(1000))
+                .await
+            {
+                warn!("RPC endpoint {} is not healthy: {:?}", endpoint.url, e);
+            }
+        }
+        if pool.check_health().await == 0 {
+            return Err(anyhow::anyhow!("No healthy RPC endpoints"));
+        }
+        Ok(pool)
+    }
+
+    pub fn endpoints(&self) -> &[RpcEndpoint] {
+        &self.endpoints
+    }
+
+    /// Asks every endpoint for its status, refreshing its health and latest
+    /// block height. Returns the number of healthy endpoints.
+    pub async fn check_health(&self) -> usize {
+        let mut healthy = 0;
+        for endpoint in self.endpoints.iter() {
+            endpoint.limiter.acquire().await;
+            let result = time_rpc(&endpoint.url, "status", endpoint.client.status()).await;
+            self.record_throttling(endpoint, &result);
+            match result {
+                Ok(status) => {
+                    let height = status.sync_info.latest_block_height.value();
+                    debug!("RPC endpoint {} is at height {}", endpoint.url, height);
+                    endpoint.record_status(height);
+                    healthy += 1;
+                }
+                Err(e) => {
+                    warn!("Status check failed for {}: {:?}", endpoint.url, e);
+                    endpoint.state.lock().unwrap().healthy = false;
+                }
+            }
+        }
+        healthy
+    }
+
+    /// The highest block height reported by any healthy endpoint.
+    pub async fn latest_block_height(&self) -> anyhow::Result<u64> {
+        self.check_health().await;
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .filter(|endpoint| endpoint.is_available(0, now))
+            .map(|endpoint| endpoint.latest_block_height())
+            .max()
+            .ok_or_else(|| anyhow::anyhow!("No healthy RPC endpoints"))
+    }
+
+    /// Picks the next endpoint, round robin, that can serve `min_height`.
+    pub fn select(&self, min_height: u64) -> Option<&RpcEndpoint> {
+        let now = Instant::now();
+        let count = self.endpoints.len();
+        let start = self.next_endpoint.fetch_add(1, Ordering::Relaxed);
+        (0..count)
+            .map(|offset| &self.endpoints[(start + offset) % count])
+            .find(|endpoint| endpoint.is_available(min_height, now))
+    }
+
+    /// Requests rejected by any endpoint's rate limiting.
+    pub fn throttled_requests(&self) -> u64 {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.limiter.throttled_requests())
+            .sum()
+    }
+
+    /// Requests held back by our own rate limiting.
+    pub fn delayed_requests(&self) -> u64 {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.limiter.delayed_requests())
+            .sum()
+    }
+
+    // Adjusts the endpoint's rate limit to the outcome of a request.
+    fn record_throttling<T, E: std::fmt::Debug>(
+        &self,
+        endpoint: &RpcEndpoint,
+        result: &Result<T, E>,
+    ) {
+        match result {
+            Ok(_) => endpoint.limiter.succeeded(),
+            Err(e) => {
+                if is_throttling_error(&format!("{:?}", e)) {
+                    endpoint.limiter.throttled();
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a request made to `endpoint`.
+    pub fn record_result<T, E: std::fmt::Debug>(
+        &self,
+        endpoint: &RpcEndpoint,
+        result: &Result<T, E>,
+    ) {
+        self.record_throttling(endpoint, result);
+        match result {
+            Ok(_) => {
+                endpoint.record_success();
+                record_progress();
+            }
+            Err(e) => {
+                debug!("RPC error from {}: {:?}", endpoint.url, e);
+                if endpoint.record_error(self.max_errors, self.eviction) {
+                    warn!(
+                        "Evicting RPC endpoint {} for {:?} after {} errors",
+                        endpoint.url, self.eviction, self.max_errors
+                    );
+                }
+            }
+        }
+    }
+
+    // Archives a response if recording is on. Failing to record is logged
+    // rather than failing the request.
+    fn record<F: FnOnce() -> ArchiveRecord>(&self, height: u64, record: F) {
+        if let Some(recorder) = &self.recorder {
+            if let Err(e) = recorder.record(height, &record()) {
+                warn!("Error recording RPC response at height {}: {:?}", height, e);
+            }
+        }
+    }
+
+    // Selects an endpoint that has reached `min_height`, refreshing endpoint
+    // heights once if none has, and waits for its rate limit.
+    async fn acquire_endpoint(&self, min_height: u64) -> anyhow::Result<&RpcEndpoint> {
+        let endpoint = match self.select(min_height) {
+            Some(endpoint) => endpoint,
+            None => {
+                self.check_health().await;
+                self.select(min_height).ok_or_else(|| {
+                    anyhow::anyhow!("No available RPC endpoint has reached block {}", min_height)
+                })?
+            }
+        };
+        endpoint.limiter.acquire().await;
+        Ok(endpoint)
+    }
+
+    /// Runs a tx_search against an endpoint that has reached `min_height`.
+    pub async fn tx_search(
+        &self,
+        query: Query,
+        page: u32,
+        per_page: u8,
+        min_height: u64,
+    ) -> anyhow::Result<TxSearchResponse> {
+        let endpoint = self.acquire_endpoint(min_height).await?;
+        let result = time_rpc(
+            &endpoint.url,
+            "tx_search",
+            endpoint
+                .client
+                .tx_search(query.clone(), false, page, per_page, Order::Ascending),
+        )
+        .await;
+        self.record_result(endpoint, &result);
+        let response = result?;
+        // Every page of a range is archived under the range's last block, so
+        // replaying files in order replays ranges in order.
+        self.record(min_height, || ArchiveRecord::TxSearch {
+            query: query.to_string(),
+            page,
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    /// Fetches the block at `height`.
+    pub async fn block(&self, height: u64) -> anyhow::Result<BlockResponse> {
+        let endpoint = self.acquire_endpoint(height).await?;
+        let result = time_rpc(
+            &endpoint.url,
+            "block",
+            endpoint.client.block(Height::try_from(height)?),
+        )
+        .await;
+        self.record_result(endpoint, &result);
+        let response = result?;
+        self.record(height, || ArchiveRecord::Block {
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+
+    /// Fetches the results (including each tx's `DeliverTx`) of the block at
+    /// `height`.
+    pub async fn block_results(&self, height: u64) -> anyhow::Result<BlockResultsResponse> {
+        let endpoint = self.acquire_endpoint(height).await?;
+        let result = time_rpc(
+            &endpoint.url,
+            "block_results",
+            endpoint.client.block_results(Height::try_from(height)?),
+        )
+        .await;
+        self.record_result(endpoint, &result);
+        let response = result?;
+        self.record(height, || ArchiveRecord::BlockResults {
+            response: response.clone(),
+        });
+        Ok(response)
+    }
+}
+
+#[test]
+fn test_client_pool_selection() {
+    let urls = vec![
+        "http://127.0.0.1:26657".to_string(),
+        "http://127.0.0.2:26657".to_string(),
+    ];
+    let endpoints = urls
+        .iter()
+        .map(|url| RpcEndpoint::new(url, RateLimiter::new(0.0, 1)).unwrap())
+        .collect();
+    let pool = ClientPool::new(endpoints, 2, Duration::from_secs(60)).unwrap();
+    // Nothing is available until it has reported its status.
+    assert!(pool.select(1).is_none());
+    pool.endpoints[0].record_status(100);
+    pool.endpoints[1].record_status(200);
+
+    // Requests alternate between the endpoints that have the heights...
+    let first = pool.select(50).unwrap().url.clone();
+    let second = pool.select(50).unwrap().url.clone();
+    assert_ne!(first, second);
+    // ...and skip lagging ones.
+    for _ in 0..3 {
+        assert_eq!(urls[1], pool.select(150).unwrap().url);
+    }
+    assert!(pool.select(250).is_none());
+
+    // Repeated errors evict an endpoint.
+    let error: Result<(), &str> = Err("connection refused");
+    pool.record_result(&pool.endpoints[1], &error);
+    assert_eq!(urls[1], pool.select(150).unwrap().url);
+    pool.record_result(&pool.endpoints[1], &error);
+    assert!(pool.select(150).is_none());
+    assert_eq!(urls[0], pool.select(50).unwrap().url);
+}
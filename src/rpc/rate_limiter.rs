@@ -0,0 +1,169 @@
+use log::warn;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How much of the configured rate is restored per successful request after
+// being throttled.
+const RECOVERY_FRACTION: f64 = 0.05;
+
+// The rate is never lowered below this fraction of the configured rate.
+const MIN_RATE_FRACTION: f64 = 0.05;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    rate: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket limiting the requests per second sent to one RPC endpoint.
+/// When the endpoint pushes back (HTTP 429/503) the rate is halved, then
+/// recovers gradually as requests succeed.
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    throttled_requests: AtomicU64,
+    delayed_requests: AtomicU64,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `requests_per_second` with bursts of up to `burst`
+    /// requests. A rate of 0 disables limiting.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        RateLimiter {
+            max_rate: requests_per_second.max(0.0),
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                rate: requests_per_second.max(0.0),
+                last_refill: Instant::now(),
+            }),
+            throttled_requests: AtomicU64::new(0),
+            delayed_requests: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_rate > 0.0
+    }
+
+    /// The current (possibly lowered) requests per second.
+    pub fn rate(&self) -> f64 {
+        self.bucket.lock().unwrap().rate
+    }
+
+    /// Requests the endpoint rejected with 429/503.
+    pub fn throttled_requests(&self) -> u64 {
+        self.throttled_requests.load(Ordering::Relaxed)
+    }
+
+    /// Requests that had to wait for the limiter.
+    pub fn delayed_requests(&self) -> u64 {
+        self.delayed_requests.load(Ordering::Relaxed)
+    }
+
+    // Takes a token if one is available at `now`, otherwise returns how long
+    // until one will be.
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * bucket.rate).min(self.burst);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate))
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut delayed = false;
+        while let Err(wait) = self.try_acquire_at(Instant::now()) {
+            if !delayed {
+                self.delayed_requests.fetch_add(1, Ordering::Relaxed);
+                delayed = true;
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Called when the endpoint throttled a request: halves the rate and
+    /// drops any saved-up burst.
+    pub fn throttled(&self) {
+        self.throttled_requests.fetch_add(1, Ordering::Relaxed);
+        if !self.is_enabled() {
+            return;
+        }
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.rate = (bucket.rate / 2.0).max(self.max_rate * MIN_RATE_FRACTION);
+        bucket.tokens = 0.0;
+        warn!(
+            "RPC endpoint throttled us, lowering rate to {:.2}/s",
+            bucket.rate
+        );
+    }
+
+    /// Called when a request succeeded: moves the rate back towards the
+    /// configured rate.
+    pub fn succeeded(&self) {
+        let mut bucket = self.bucket.lock().unwrap();
+        if bucket.rate < self.max_rate {
+            bucket.rate = (bucket.rate + self.max_rate * RECOVERY_FRACTION).min(self.max_rate);
+        }
+    }
+}
+
+/// Whether an RPC error is the endpoint telling us to slow down. Only
+/// status phrases count: bare codes turn up in heights and hashes too.
+pub fn is_throttling_error(error: &str) -> bool {
+    [
+        "Too Many Requests",
+        "Service Unavailable",
+        "status code: 429",
+        "status code: 503",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+#[test]
+fn test_rate_limiter() {
+    let limiter = RateLimiter::new(10.0, 2);
+    let start = Instant::now();
+    assert!(limiter.try_acquire_at(start).is_ok());
+    assert!(limiter.try_acquire_at(start).is_ok());
+    // The burst is used up; the next token arrives after 100ms.
+    let wait = limiter.try_acquire_at(start).unwrap_err();
+    assert_eq!(100, wait.as_millis());
+    assert!(limiter
+        .try_acquire_at(start + Duration::from_millis(100))
+        .is_ok());
+
+    limiter.throttled();
+    assert_eq!(5.0, limiter.rate());
+    assert_eq!(1, limiter.throttled_requests());
+    let wait = limiter
+        .try_acquire_at(start + Duration::from_millis(100))
+        .unwrap_err();
+    assert_eq!(200, wait.as_millis());
+    for _ in 0..100 {
+        limiter.succeeded();
+    }
+    assert_eq!(10.0, limiter.rate());
+
+    assert!(is_throttling_error(
+        "HTTP status client error (429 Too Many Requests)"
+    ));
+    assert!(is_throttling_error("request failed with status code: 503"));
+    assert!(!is_throttling_error("connection refused"));
+    assert!(!is_throttling_error("no block at height 14290"));
+    assert!(!is_throttling_error("tx 503A9F not found on port 26657"));
+}
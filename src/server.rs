@@ -0,0 +1,113 @@
+use crate::health::HealthChecks;
+use crate::metrics::encode_metrics;
+use crate::shutdown::ShutdownSignal;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info};
+use prometheus::{Encoder, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+// 200 with "ok", or 503 with why the check failed.
+fn check_response(result: anyhow::Result<()>) -> Response<Body> {
+    match result {
+        Ok(()) => Response::new(Body::from("ok")),
+        Err(e) => {
+            let mut response = Response::new(Body::from(format!("{:#}", e)));
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            response
+        }
+    }
+}
+
+async fn handle_request(
+    request: Request<Body>,
+    checks: HealthChecks,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+        return Ok(response);
+    }
+    let response = match request.uri().path() {
+        "/metrics" => match encode_metrics() {
+            Ok(metrics) => {
+                let mut response = Response::new(Body::from(metrics));
+                response.headers_mut().insert(
+                    CONTENT_TYPE,
+                    TextEncoder::new().format_type().parse().unwrap(),
+                );
+                response
+            }
+            Err(e) => {
+                error!("Error encoding metrics: {:?}", e);
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                response
+            }
+        },
+        "/healthz" => check_response(checks.check_liveness()),
+        "/readyz" => check_response(checks.check_readiness().await),
+        _ => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    };
+    Ok(response)
+}
+
+/// Serves Prometheus metrics on `/metrics` and the liveness and readiness
+/// checks on `/healthz` and `/readyz` until `shutdown` is requested.
+pub async fn serve(
+    address: SocketAddr,
+    checks: HealthChecks,
+    shutdown: ShutdownSignal,
+) -> anyhow::Result<()> {
+    let make_service = make_service_fn(move |_conn| {
+        let checks = checks.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(request, checks.clone())
+            }))
+        }
+    });
+    let server = Server::try_bind(&address)?.serve(make_service);
+    info!("Serving metrics and health checks on http://{}", address);
+    server
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_routes() {
+    use crate::config::IndexerConfig;
+    use crate::health::record_progress;
+    use crate::metrics::TXS_PROCESSED;
+    use hyper::body::to_bytes;
+    let mut config = IndexerConfig::with_defaults();
+    config.liveness_timeout_seconds = 3600;
+    let checks = HealthChecks::from_config(&config);
+    let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+    TXS_PROCESSED.inc();
+    let response = handle_request(get("/metrics"), checks.clone())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    let body = to_bytes(response.into_body()).await.unwrap();
+    assert!(String::from_utf8(body.to_vec())
+        .unwrap()
+        .contains("indexer_txs_processed_total"));
+
+    record_progress();
+    let response = handle_request(get("/healthz"), checks.clone())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = handle_request(get("/nothing"), checks).await.unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
@@ -0,0 +1,96 @@
+use log::info;
+use tokio::sync::watch;
+
+/// Tells long-running work that the indexer has been asked to stop, so it
+/// can stop starting new work, finish what is in flight and save its
+/// progress. Clones all observe the same signal.
+#[derive(Clone, Debug)]
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+/// Triggers the `ShutdownSignal` it was created with.
+#[derive(Debug)]
+pub struct ShutdownTrigger {
+    sender: watch::Sender<bool>,
+}
+
+impl ShutdownTrigger {
+    pub fn shutdown(&self) {
+        // Nobody listening just means there is nothing left to stop.
+        let _ = self.sender.send(true);
+    }
+}
+
+impl ShutdownSignal {
+    pub fn new() -> (ShutdownTrigger, ShutdownSignal) {
+        let (sender, receiver) = watch::channel(false);
+        (ShutdownTrigger { sender }, ShutdownSignal { receiver })
+    }
+
+    /// A signal that never fires, for callers that run to completion.
+    pub fn never() -> Self {
+        let (_trigger, signal) = ShutdownSignal::new();
+        signal
+    }
+
+    /// A signal that fires on SIGINT (ctrl-c) or SIGTERM.
+    pub fn from_os_signals() -> Self {
+        let (trigger, signal) = ShutdownSignal::new();
+        tokio::spawn(async move {
+            wait_for_os_signal().await;
+            info!("Shutdown requested, finishing in-flight work");
+            trigger.shutdown();
+        });
+        signal
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Waits until shutdown is requested.
+    pub async fn wait(&self) {
+        let mut receiver = self.receiver.clone();
+        while !*receiver.borrow() {
+            if receiver.changed().await.is_err() {
+                // The trigger is gone, so shutdown can never be requested.
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_os_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        Err(e) => {
+            log::warn!("Unable to listen for SIGTERM: {:?}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_os_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[tokio::test]
+async fn test_shutdown_signal() {
+    let (trigger, signal) = ShutdownSignal::new();
+    let observer = signal.clone();
+    assert!(!observer.is_shutdown());
+    let waiter = tokio::spawn(async move { observer.wait().await });
+    trigger.shutdown();
+    waiter.await.unwrap();
+    assert!(signal.is_shutdown());
+    assert!(!ShutdownSignal::never().is_shutdown());
+}
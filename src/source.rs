@@ -0,0 +1,347 @@
+pub mod archive_source;
+pub mod db_source;
+pub mod file_source;
+pub mod rpc_source;
+pub mod websocket_source;
+
+use crate::config::IndexerConfig;
+use crate::health::record_progress;
+use crate::indexing::event_map::EventMap;
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::indexing::msg_set::MsgSet;
+use crate::indexing::tx::{process_parsed, process_parsed_v1beta, MessageIndexSummary};
+use crate::indexing::tx_events::{TxEvent, TxEvents};
+use crate::metrics::TXS_PROCESSED;
+use crate::shutdown::ShutdownSignal;
+use crate::util::height_ranges::HeightRange;
+use crate::util::history_util::tx_bytes_hash;
+use crate::util::message_key::{TX_CODE_KEY, TX_HASH_KEY};
+use crate::util::transaction_util::{insert_failed_transaction, insert_transaction};
+use async_trait::async_trait;
+use cosmos_sdk_proto::cosmos::tx::v1beta1::Tx as TxV1;
+use cosmrs::tx::Tx;
+use diesel::Connection;
+use log::{debug, error, info, warn};
+use prost::Message;
+use tendermint::abci::Transaction;
+use tendermint_rpc::endpoint::tx::Response;
+use tendermint_rpc::event::TxInfo;
+
+/// A transaction to index along with its events, wherever it came from.
+#[derive(Clone, Debug)]
+pub struct SourceTx {
+    pub height: u64,
+    pub index: u32,
+    pub hash: String,
+    pub tx: Vec<u8>,
+    pub events: TxEvents,
+    /// The events each message emitted, in message order, split out of the
+    /// transaction's events or else read from its log. Empty when neither
+    /// breaks them down.
+    pub message_events: Vec<TxEvents>,
+    /// The transaction's result code, 0 if it succeeded.
+    pub code: u32,
+    pub codespace: String,
+    pub log: String,
+    /// The RPC response the transaction was read from, if there was one.
+    /// This is what gets stored in the `transaction` table.
+    pub response: Option<Response>,
+}
+
+impl SourceTx {
+    pub fn from_response(response: &Response) -> anyhow::Result<Self> {
+        let mut events = TxEvents::from_abci(&response.tx_result.events);
+        let code = response.tx_result.code.value();
+        // The tx.* keys a websocket subscription gets from tendermint.
+        let mut tx_attributes = vec![];
+        if events.get("tx.height").is_none() {
+            tx_attributes.push(("height".to_string(), response.height.to_string()));
+        }
+        if events.get(TX_HASH_KEY).is_none() {
+            tx_attributes.push(("hash".to_string(), response.hash.to_string()));
+        }
+        if events.get(TX_CODE_KEY).is_none() {
+            tx_attributes.push(("code".to_string(), code.to_string()));
+        }
+        if !tx_attributes.is_empty() {
+            events.push(TxEvent::new("tx", tx_attributes));
+        }
+        // The log merges each message's events of a type into one, losing
+        // their order, which the transaction's own events still have.
+        let log_events = TxEvents::from_message_logs(&response.tx_result.log.to_string());
+        let ordered_events = events.split_by_action();
+        let message_events = if ordered_events.len() == log_events.len() {
+            ordered_events
+        } else {
+            log_events
+        };
+        Ok(SourceTx {
+            height: response.height.value(),
+            index: response.index,
+            hash: response.hash.to_string(),
+            tx: response.tx.as_bytes().to_vec(),
+            events,
+            message_events,
+            code,
+            codespace: response.tx_result.codespace.to_string(),
+            log: response.tx_result.log.to_string(),
+            response: Some(response.clone()),
+        })
+    }
+
+    /// A transaction delivered by a websocket subscription, whose events
+    /// arrive already flattened.
+    ///
+    /// The subscription gives a transaction's log but not its result code.
+    /// A failed transaction logs its error where a successful one logs its
+    /// messages as JSON, so failures are told apart by the log and recorded
+    /// with code 1 and no codespace.
+    pub fn from_tx_info(tx_info: TxInfo, mut events: EventMap) -> Self {
+        let log = tx_info.result.log.clone().unwrap_or_default();
+        let code = match events
+            .get(TX_CODE_KEY)
+            .and_then(|codes| codes.first())
+            .and_then(|code| code.parse::<u32>().ok())
+        {
+            Some(code) => code,
+            None if log.is_empty() || serde_json::from_str::<serde_json::Value>(&log).is_ok() => 0,
+            None => 1,
+        };
+        events.insert(TX_CODE_KEY.to_string(), vec![code.to_string()]);
+        let hash = events
+            .get(TX_HASH_KEY)
+            .and_then(|hashes| hashes.first())
+            .cloned()
+            .unwrap_or_else(|| tx_bytes_hash(&Transaction::from(tx_info.tx.clone())).to_string());
+        SourceTx {
+            height: tx_info.height as u64,
+            index: tx_info.index.unwrap_or_default(),
+            hash,
+            tx: tx_info.tx,
+            events: TxEvents::from_event_map(&events),
+            message_events: TxEvents::from_message_logs(&log),
+            code,
+            codespace: String::new(),
+            log,
+            response: None,
+        }
+    }
+
+    /// Whether the chain rejected the transaction. Its messages changed
+    /// nothing, even if some of its events look like they did.
+    pub fn failed(&self) -> bool {
+        self.code != 0
+    }
+
+    /// The (height, tx index) position of the transaction in the chain.
+    pub fn position(&self) -> (u64, u32) {
+        (self.height, self.index)
+    }
+}
+
+/// Transactions from a `TxSource`, in (height, tx index) order.
+#[derive(Debug, Default)]
+pub struct TxBatch {
+    /// The blocks the batch covers, when the source knows them. Blocks in
+    /// the range without transactions had none to index.
+    pub range: Option<HeightRange>,
+    pub txs: Vec<SourceTx>,
+}
+
+/// Somewhere transactions to index come from: RPC nodes, a websocket
+/// subscription, a local archive or the `transaction` table.
+///
+/// Sources may borrow the registry's diesel connection, which can't be
+/// shared between threads, so their futures aren't `Send`.
+#[async_trait(?Send)]
+pub trait TxSource {
+    /// The next batch of transactions, or None once the source is
+    /// exhausted. Batches come in height order.
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>>;
+}
+
+/// Indexes a single transaction from any source: stores its response if
+/// configured to, decodes it and hands its messages to the registry.
+///
+/// Everything the transaction writes through the registry's connection is
+/// committed together, so a transaction that fails, or is interrupted by a
+/// shutdown, leaves nothing half-written. A failed transaction is returned
+/// as an error, so the range it is in isn't recorded as indexed and is left
+/// for backfill; a message that fails is logged and skipped without its
+/// transaction's other messages.
+pub fn index_tx(
+    source_tx: &SourceTx,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+) -> anyhow::Result<()> {
+    let result = match &registry.db {
+        Some(db) => db.transaction::<_, anyhow::Error, _>(|| {
+            index_tx_messages(source_tx, registry, config, msg_set)
+        }),
+        None => index_tx_messages(source_tx, registry, config, msg_set),
+    };
+    record_progress();
+    let summary = result.map_err(|e| {
+        e.context(format!(
+            "indexing tx {} at height {}",
+            source_tx.hash, source_tx.height
+        ))
+    })?;
+    TXS_PROCESSED.inc();
+    debug!(
+        "tx {}: indexed {} of {} messages, {} unhandled",
+        source_tx.hash,
+        summary.indexed,
+        summary.total(),
+        summary.unhandled
+    );
+    for failure in summary.failures.iter() {
+        warn!(
+            "tx {} at height {}: msg {} ({}) not indexed",
+            source_tx.hash, source_tx.height, failure.msg_index, failure.type_url
+        );
+    }
+    Ok(())
+}
+
+fn index_tx_messages(
+    source_tx: &SourceTx,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+) -> anyhow::Result<MessageIndexSummary> {
+    if config.write_transactions_in_database {
+        if let Some(response) = &source_tx.response {
+            insert_transaction(response, registry)?;
+        }
+    }
+    if source_tx.failed() {
+        if let Some(db) = &registry.db {
+            insert_failed_transaction(db, source_tx)?;
+        }
+        if !registry.indexes_failed_txs() {
+            debug!(
+                "skipping failed tx {} (code {}): {}",
+                source_tx.hash, source_tx.code, source_tx.log
+            );
+            return Ok(MessageIndexSummary::default());
+        }
+    }
+    let events = &source_tx.events;
+    match Tx::from_bytes(&source_tx.tx) {
+        Ok(unmarshalled_tx) => process_parsed(
+            registry,
+            &unmarshalled_tx,
+            events,
+            &source_tx.message_events,
+            msg_set,
+        )
+        .map_err(|e| e.context(format!("{:?}", unmarshalled_tx))),
+        Err(e) => {
+            warn!(
+                "Error unmarshalling: {:?} via Tx::from_bytes, trying v1beta decode",
+                e
+            );
+            info!(
+                "tx {} at height {}:\n{:?}",
+                source_tx.hash, source_tx.height, source_tx.response
+            );
+            match TxV1::decode(source_tx.tx.as_slice()) {
+                Ok(unmarshalled_tx) => {
+                    info!("decoded response debug:\n{:?}", unmarshalled_tx);
+                    process_parsed_v1beta(
+                        registry,
+                        &unmarshalled_tx,
+                        events,
+                        &source_tx.message_events,
+                        msg_set,
+                    )
+                }
+                Err(e) => {
+                    error!("Error decoding: {:?}", e);
+                    Ok(MessageIndexSummary::default())
+                }
+            }
+        }
+    }
+}
+
+/// Indexes every batch from `source` until it is exhausted or `shutdown`
+/// is requested. Returns the number of transactions indexed.
+pub async fn index_source(
+    source: &mut dyn TxSource,
+    registry: &IndexerRegistry,
+    config: &IndexerConfig,
+    msg_set: MsgSet,
+    shutdown: &ShutdownSignal,
+) -> anyhow::Result<usize> {
+    let mut indexed = 0;
+    while !shutdown.is_shutdown() {
+        let batch = match source.next_batch().await? {
+            Some(batch) => batch,
+            None => break,
+        };
+        for source_tx in batch.txs.iter() {
+            index_tx(source_tx, registry, config, msg_set.clone())?;
+        }
+        indexed += batch.txs.len();
+        if let Some(range) = batch.range {
+            debug!(
+                "indexed {} transactions in blocks {}",
+                batch.txs.len(),
+                range
+            );
+        }
+    }
+    Ok(indexed)
+}
+
+#[cfg(test)]
+pub(crate) fn test_response(height: u64, index: u32) -> Response {
+    use tendermint::abci::responses::{DeliverTx, Event};
+    use tendermint::abci::tag::Tag;
+    use tendermint::block::Height;
+    let mut hash_bytes = [0u8; 32];
+    hash_bytes[..8].copy_from_slice(&height.to_be_bytes());
+    hash_bytes[8..12].copy_from_slice(&index.to_be_bytes());
+    Response {
+        hash: tendermint::abci::transaction::Hash::new(hash_bytes),
+        height: Height::try_from(height).unwrap(),
+        index,
+        tx_result: DeliverTx {
+            events: vec![Event {
+                type_str: "message".to_string(),
+                attributes: vec![Tag {
+                    key: "sender".parse().unwrap(),
+                    value: "juno1sender".parse().unwrap(),
+                }],
+            }],
+            ..Default::default()
+        },
+        tx: Transaction::from(vec![index as u8]),
+        proof: None,
+    }
+}
+
+#[test]
+fn test_source_tx_from_response() {
+    let response = test_response(42, 3);
+    let source_tx = SourceTx::from_response(&response).unwrap();
+    assert_eq!((42, 3), source_tx.position());
+    assert_eq!(response.hash.to_string(), source_tx.hash);
+    assert_eq!(vec!["42".to_string()], source_tx.events["tx.height"]);
+    assert_eq!(vec![source_tx.hash.clone()], source_tx.events[TX_HASH_KEY]);
+    assert_eq!(vec!["0".to_string()], source_tx.events[TX_CODE_KEY]);
+    assert!(!source_tx.failed());
+
+    let mut failed = test_response(43, 0);
+    failed.tx_result.code = tendermint::abci::Code::from(5);
+    let source_tx = SourceTx::from_response(&failed).unwrap();
+    assert!(source_tx.failed());
+    assert_eq!(vec!["5".to_string()], source_tx.events[TX_CODE_KEY]);
+    assert_eq!(
+        vec!["juno1sender".to_string()],
+        source_tx.events["message.sender"]
+    );
+}
@@ -0,0 +1,160 @@
+use super::{SourceTx, TxBatch, TxSource};
+use crate::block_parser::block_tx_responses;
+use crate::historical_parser::order_transactions;
+use crate::rpc::archive::{archive_files, read_archive_file, ArchiveRecord};
+use crate::util::block_util::insert_block;
+use crate::util::height_ranges::HeightRange;
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use log::{info, warn};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tendermint_rpc::endpoint::block::Response as BlockResponse;
+use tendermint_rpc::endpoint::block_results::Response as BlockResultsResponse;
+use tendermint_rpc::endpoint::tx::Response;
+
+/// Replays the RPC responses recorded with `ARCHIVE_RECORD_DIR`, one archive
+/// file per batch, so a database can be rebuilt without a node.
+pub struct ArchiveSource<'a> {
+    files: VecDeque<(HeightRange, PathBuf)>,
+    // Where to save replayed blocks, as the blocks ingestion mode would.
+    db: Option<&'a PgConnection>,
+    save_all_blocks: bool,
+    last_position: Option<(u64, u32)>,
+}
+
+impl<'a> ArchiveSource<'a> {
+    pub fn open(
+        dir: &Path,
+        db: Option<&'a PgConnection>,
+        save_all_blocks: bool,
+    ) -> anyhow::Result<Self> {
+        let files = archive_files(dir)?;
+        info!(
+            "Replaying {} archive files from {}",
+            files.len(),
+            dir.display()
+        );
+        Ok(ArchiveSource {
+            files: files.into(),
+            db,
+            save_all_blocks,
+            last_position: None,
+        })
+    }
+
+    // The transactions recorded in one archive file: tx_search pages as they
+    // are, and blocks paired up with their results.
+    fn read_file(&self, path: &Path) -> anyhow::Result<Vec<Response>> {
+        let mut txs = vec![];
+        let mut blocks: BTreeMap<u64, BlockResponse> = BTreeMap::new();
+        let mut results: BTreeMap<u64, BlockResultsResponse> = BTreeMap::new();
+        for record in read_archive_file(path)? {
+            match record {
+                ArchiveRecord::TxSearch { response, .. } => txs.extend(response.txs),
+                ArchiveRecord::Block { response } => {
+                    blocks.insert(response.block.header.height.value(), response);
+                }
+                ArchiveRecord::BlockResults { response } => {
+                    results.insert(response.height.value(), response);
+                }
+            }
+        }
+        for (height, block_response) in blocks {
+            let block_results = match results.get(&height) {
+                Some(block_results) => block_results,
+                None => {
+                    warn!("No results recorded for block {}, skipping it", height);
+                    continue;
+                }
+            };
+            let tx_responses = block_tx_responses(&block_response.block, block_results)?;
+            if let Some(db) = self.db {
+                if !tx_responses.is_empty() || self.save_all_blocks {
+                    insert_block(
+                        db,
+                        &block_response.block_id.hash.to_string(),
+                        &block_response.block,
+                    )?;
+                }
+            }
+            txs.extend(tx_responses);
+        }
+        Ok(txs)
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> TxSource for ArchiveSource<'a> {
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>> {
+        let (range, path) = match self.files.pop_front() {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let recorded = self.read_file(&path)?;
+        // Ranges recorded in consecutive files share a block.
+        let txs = order_transactions(recorded, self.last_position)
+            .iter()
+            .map(SourceTx::from_response)
+            .collect::<anyhow::Result<Vec<SourceTx>>>()?;
+        if let Some(last) = txs.last() {
+            self.last_position = Some(last.position());
+        }
+        Ok(Some(TxBatch {
+            range: Some(range),
+            txs,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_archive_source() {
+    use super::test_response;
+    use crate::rpc::archive::ArchiveRecorder;
+    use tendermint_rpc::endpoint::tx_search::Response as TxSearchResponse;
+    let dir =
+        std::env::temp_dir().join(format!("dao_indexer_archive_source_{}", std::process::id()));
+    let recorder = ArchiveRecorder::new(&dir, 100).unwrap();
+    let record = |txs: Vec<Response>| ArchiveRecord::TxSearch {
+        query: String::new(),
+        page: 1,
+        response: TxSearchResponse {
+            total_count: txs.len() as u32,
+            txs,
+        },
+    };
+    // Pages recorded out of order, and a block shared between two files.
+    recorder
+        .record(150, &record(vec![test_response(120, 0)]))
+        .unwrap();
+    recorder
+        .record(
+            100,
+            &record(vec![
+                test_response(100, 1),
+                test_response(99, 0),
+                test_response(100, 0),
+            ]),
+        )
+        .unwrap();
+    recorder
+        .record(
+            99,
+            &record(vec![test_response(50, 0), test_response(99, 0)]),
+        )
+        .unwrap();
+    recorder
+        .record(99, &record(vec![test_response(99, 0)]))
+        .unwrap();
+
+    let mut source = ArchiveSource::open(&dir, None, false).unwrap();
+    let mut positions = vec![];
+    while let Some(batch) = source.next_batch().await.unwrap() {
+        positions.extend(batch.txs.iter().map(|tx| tx.position()));
+    }
+    assert_eq!(
+        vec![(50, 0), (99, 0), (100, 0), (100, 1), (120, 0)],
+        positions
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
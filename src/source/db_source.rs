@@ -0,0 +1,44 @@
+use super::{SourceTx, TxBatch, TxSource};
+use crate::historical_parser::order_transactions;
+use crate::util::height_ranges::HeightRange;
+use crate::util::transaction_util::get_transactions_in_range;
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use std::collections::VecDeque;
+
+/// Transactions previously stored in the `transaction` table (see
+/// `STORE_TRANSACTIONS`), one page of blocks per batch.
+pub struct DbSource<'a> {
+    db: &'a PgConnection,
+    ranges: VecDeque<HeightRange>,
+}
+
+impl<'a> DbSource<'a> {
+    /// Reads the stored transactions in `range`, `page_size` blocks at a
+    /// time.
+    pub fn new(db: &'a PgConnection, range: HeightRange, page_size: u64) -> Self {
+        DbSource {
+            db,
+            ranges: range.split(page_size).into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> TxSource for DbSource<'a> {
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>> {
+        let range = match self.ranges.pop_front() {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+        let stored = get_transactions_in_range(self.db, &range)?;
+        let txs = order_transactions(stored, None)
+            .iter()
+            .map(SourceTx::from_response)
+            .collect::<anyhow::Result<Vec<SourceTx>>>()?;
+        Ok(Some(TxBatch {
+            range: Some(range),
+            txs,
+        }))
+    }
+}
@@ -0,0 +1,101 @@
+use super::{SourceTx, TxBatch, TxSource};
+use crate::util::height_ranges::HeightRange;
+use async_trait::async_trait;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+use tendermint_rpc::endpoint::tx::Response;
+
+/// Transactions read from a local file of RPC `tx` responses, one JSON
+/// response per line in height order, one block per batch. Files ending in
+/// `.gz` are decompressed.
+pub struct FileSource {
+    lines: Lines<Box<dyn BufRead>>,
+    // The first transaction of the next block, read while looking for the
+    // end of the current one.
+    pending: Option<SourceTx>,
+}
+
+impl FileSource {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow::anyhow!("Error opening {}: {:?}", path.display(), e))?;
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "gz")
+        {
+            let decoder = MultiGzDecoder::new(file);
+            return Ok(FileSource::from_reader(Box::new(BufReader::new(decoder))));
+        }
+        Ok(FileSource::from_reader(Box::new(BufReader::new(file))))
+    }
+
+    pub fn from_reader(reader: Box<dyn BufRead>) -> Self {
+        FileSource {
+            lines: reader.lines(),
+            pending: None,
+        }
+    }
+
+    fn read_tx(&mut self) -> anyhow::Result<Option<SourceTx>> {
+        for line in self.lines.by_ref() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response: Response = serde_json::from_str(&line)?;
+            return Ok(Some(SourceTx::from_response(&response)?));
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait(?Send)]
+impl TxSource for FileSource {
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>> {
+        let first = match self.pending.take() {
+            Some(source_tx) => source_tx,
+            None => match self.read_tx()? {
+                Some(source_tx) => source_tx,
+                None => return Ok(None),
+            },
+        };
+        let height = first.height;
+        let mut txs = vec![first];
+        while let Some(source_tx) = self.read_tx()? {
+            if source_tx.height != height {
+                self.pending = Some(source_tx);
+                break;
+            }
+            txs.push(source_tx);
+        }
+        Ok(Some(TxBatch {
+            range: Some(HeightRange::new(height, height)),
+            txs,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_file_source() {
+    use super::test_response;
+    use std::io::Cursor;
+    let lines: Vec<String> = [(10, 0), (10, 1), (12, 0)]
+        .iter()
+        .map(|(height, index)| serde_json::to_string(&test_response(*height, *index)).unwrap())
+        .collect();
+    // Blank lines are ignored.
+    let contents = format!("{}\n\n{}\n", lines[..2].join("\n"), lines[2]);
+    let mut source = FileSource::from_reader(Box::new(Cursor::new(contents.into_bytes())));
+
+    let batch = source.next_batch().await.unwrap().unwrap();
+    assert_eq!(Some(HeightRange::new(10, 10)), batch.range);
+    let positions: Vec<(u64, u32)> = batch.txs.iter().map(|tx| tx.position()).collect();
+    assert_eq!(vec![(10, 0), (10, 1)], positions);
+
+    let batch = source.next_batch().await.unwrap().unwrap();
+    assert_eq!(1, batch.txs.len());
+    assert_eq!((12, 0), batch.txs[0].position());
+    assert!(source.next_batch().await.unwrap().is_none());
+}
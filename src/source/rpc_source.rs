@@ -0,0 +1,80 @@
+use super::{SourceTx, TxBatch, TxSource};
+use crate::config::IndexerConfig;
+use crate::historical_parser::{fetch_range, order_transactions};
+use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::rpc::client_pool::ClientPool;
+use crate::shutdown::ShutdownSignal;
+use crate::util::height_ranges::HeightRange;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+
+/// Transactions fetched from RPC nodes with the configured ingestion mode,
+/// one page of blocks per batch.
+pub struct RpcSource<'a> {
+    clients: ClientPool,
+    registry: &'a IndexerRegistry,
+    config: &'a IndexerConfig,
+    ranges: VecDeque<HeightRange>,
+    last_position: Option<(u64, u32)>,
+    shutdown: ShutdownSignal,
+}
+
+impl<'a> RpcSource<'a> {
+    pub fn new(
+        clients: ClientPool,
+        registry: &'a IndexerRegistry,
+        config: &'a IndexerConfig,
+        range: HeightRange,
+        shutdown: ShutdownSignal,
+    ) -> Self {
+        RpcSource {
+            clients,
+            registry,
+            config,
+            ranges: range.split(config.block_page_size).into(),
+            last_position: None,
+            shutdown,
+        }
+    }
+
+    /// Connects to the configured RPC nodes to read the blocks in `range`.
+    pub async fn connect(
+        registry: &'a IndexerRegistry,
+        config: &'a IndexerConfig,
+        range: HeightRange,
+        shutdown: ShutdownSignal,
+    ) -> anyhow::Result<RpcSource<'a>> {
+        let clients = ClientPool::connect(config).await?;
+        Ok(RpcSource::new(clients, registry, config, range, shutdown))
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> TxSource for RpcSource<'a> {
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>> {
+        let range = match self.ranges.pop_front() {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+        let fetched = fetch_range(
+            &self.clients,
+            self.config,
+            self.registry,
+            range,
+            &self.shutdown,
+        )
+        .await?;
+        // Consecutive pages share a block, so drop what the last one returned.
+        let txs = order_transactions(fetched, self.last_position)
+            .iter()
+            .map(SourceTx::from_response)
+            .collect::<anyhow::Result<Vec<SourceTx>>>()?;
+        if let Some(last) = txs.last() {
+            self.last_position = Some(last.position());
+        }
+        Ok(Some(TxBatch {
+            range: Some(range),
+            txs,
+        }))
+    }
+}
@@ -0,0 +1,103 @@
+use super::{SourceTx, TxBatch, TxSource};
+use crate::config::IndexerConfig;
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::{debug, error, info};
+use tendermint_rpc::event::{Event, EventData};
+use tendermint_rpc::query::EventType;
+use tendermint_rpc::{SubscriptionClient, WebSocketClient};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::task::JoinHandle;
+
+/// New transactions from a websocket subscription, one transaction per
+/// batch. Events are buffered from the moment the subscription is opened,
+/// so they can be indexed once the historical sync has caught up.
+pub struct WebSocketSource {
+    client: WebSocketClient,
+    driver_handle: JoinHandle<Result<(), tendermint_rpc::Error>>,
+    forward_handle: JoinHandle<()>,
+    events: UnboundedReceiver<Result<Event, tendermint_rpc::Error>>,
+}
+
+impl WebSocketSource {
+    /// Connects to the configured websocket and starts buffering `Tx`
+    /// events.
+    pub async fn start(config: &IndexerConfig) -> anyhow::Result<Self> {
+        info!(
+            "Subscribing to transactions at {}",
+            config.tendermint_websocket_url
+        );
+        let (client, driver) =
+            WebSocketClient::new::<&str>(&config.tendermint_websocket_url).await?;
+        let driver_handle = tokio::spawn(async move { driver.run().await });
+
+        // Subscribe to transactions (can also add blocks but just Tx for now)
+        let mut subs = client.subscribe(EventType::Tx.into()).await?;
+        let (sender, events) = unbounded_channel();
+        let forward_handle = tokio::spawn(async move {
+            while let Some(res) = subs.next().await {
+                if sender.send(res).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(WebSocketSource {
+            client,
+            driver_handle,
+            forward_handle,
+            events,
+        })
+    }
+
+    /// Closes the websocket and waits for the connection to shut down.
+    pub async fn close(self) {
+        self.forward_handle.abort();
+        // Signal to the driver to terminate.
+        match self.client.close() {
+            Ok(val) => info!("closed {:?}", val),
+            Err(e) => error!("Error closing client {:?}", e),
+        }
+        // Await the driver's termination to ensure proper connection closure.
+        match self.driver_handle.await {
+            Ok(Err(e)) => error!("websocket driver failed: {:?}", e),
+            Err(e) => error!("websocket driver panicked: {:?}", e),
+            Ok(Ok(())) => {}
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl TxSource for WebSocketSource {
+    /// Waits for the next transaction, logging and skipping events that
+    /// can't be read. Returns None once the subscription has ended.
+    async fn next_batch(&mut self) -> anyhow::Result<Option<TxBatch>> {
+        while let Some(res) = self.events.recv().await {
+            let ev = match res {
+                Ok(ev) => ev,
+                Err(e) => {
+                    error!("Skipping bad event: {:?}", e);
+                    continue;
+                }
+            };
+            match ev.data {
+                EventData::NewBlock { block, .. } => debug!("{:?}", block),
+                EventData::Tx { tx_result, .. } => match ev.events {
+                    Some(events) => {
+                        return Ok(Some(TxBatch {
+                            range: None,
+                            txs: vec![SourceTx::from_tx_info(tx_result, events)],
+                        }))
+                    }
+                    None => error!(
+                        "Skipping tx event at height {} with no events",
+                        tx_result.height
+                    ),
+                },
+                result => {
+                    error!("Unexpected result {:?}", result)
+                }
+            }
+        }
+        Ok(None)
+    }
+}
@@ -1,10 +1,18 @@
+pub mod bank_transfer;
+pub mod block_util;
 pub mod contract_util;
 pub mod dao;
 pub mod debug;
+pub mod delegation;
+pub mod failed_requests;
 pub mod gov_token;
+pub mod height_ranges;
 pub mod history_util;
+pub mod ibc;
 pub mod insert_marketing_info;
+pub mod message_key;
 pub mod query_stream;
 pub mod schema_dumping;
+pub mod sync_state;
 pub mod transaction_util;
 pub mod update_balance;
@@ -0,0 +1,197 @@
+use crate::db::models::NewBankTransfer;
+use crate::indexing::tx_events::TxEvents;
+use crate::util::message_key::{tx_height, MessageKey};
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use cosmrs::AccountId;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// One coin moved from one account to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BankTransfer {
+    pub from_address: String,
+    pub to_address: String,
+    pub denom: String,
+    pub amount: BigDecimal,
+}
+
+impl BankTransfer {
+    pub fn new(from_address: &str, to_address: &str, denom: &str, amount: BigDecimal) -> Self {
+        BankTransfer {
+            from_address: from_address.to_string(),
+            to_address: to_address.to_string(),
+            denom: denom.to_string(),
+            amount,
+        }
+    }
+}
+
+/// Parses a coin list as the SDK prints it in events, like "10ujuno,5uatom".
+pub fn parse_coins(coins: &str) -> anyhow::Result<Vec<(String, BigDecimal)>> {
+    coins
+        .split(',')
+        .filter(|coin| !coin.is_empty())
+        .map(|coin| {
+            let denom_start = coin
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(|| anyhow!("no denom in coin {}", coin))?;
+            let (amount, denom) = coin.split_at(denom_start);
+            Ok((denom.to_string(), BigDecimal::from_str(amount)?))
+        })
+        .collect()
+}
+
+/// The module account transaction fees are paid to. Its address is derived
+/// from this name, the same on every chain but for its prefix.
+const FEE_COLLECTOR: &[u8] = b"fee_collector";
+
+fn is_fee_collector(address: &str) -> bool {
+    match AccountId::from_str(address) {
+        Ok(account) => account.to_bytes() == Sha256::digest(FEE_COLLECTOR)[..20],
+        Err(_) => false,
+    }
+}
+
+/// The transfers in the `transfer` events of `events`, one per coin. Fees
+/// paid to the fee collector aren't transfers of the message and are left
+/// out.
+pub fn transfers_from_events(events: &TxEvents) -> anyhow::Result<Vec<BankTransfer>> {
+    let mut transfers = vec![];
+    for record in events.records("transfer") {
+        let recipient = record.require("recipient")?;
+        if is_fee_collector(recipient) {
+            continue;
+        }
+        let sender = record.require("sender")?;
+        for (denom, amount) in parse_coins(record.require("amount")?)? {
+            transfers.push(BankTransfer::new(sender, recipient, &denom, amount));
+        }
+    }
+    Ok(transfers)
+}
+
+/// Stores the transfers a message made, linking either side that is a DAO
+/// to it.
+pub fn insert_bank_transfers(
+    db: &PgConnection,
+    events: &TxEvents,
+    transfers: &[BankTransfer],
+) -> anyhow::Result<()> {
+    if transfers.is_empty() {
+        return Ok(());
+    }
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    let addresses: Vec<&str> = transfers
+        .iter()
+        .flat_map(|transfer| [transfer.from_address.as_str(), transfer.to_address.as_str()])
+        .collect();
+    let daos: Vec<String> = {
+        use crate::db::schema::dao::dsl::*;
+        dao.select(contract_address)
+            .filter(contract_address.eq_any(addresses))
+            .load(db)?
+    };
+    let dao_address = |address: &str| {
+        daos.iter()
+            .find(|dao| *dao == address)
+            .map(|dao| dao.as_str())
+    };
+    let rows: Vec<NewBankTransfer> = transfers
+        .iter()
+        .enumerate()
+        .map(|(event_index, transfer)| NewBankTransfer {
+            tx_hash: message_key.tx_hash.clone(),
+            msg_index: message_key.msg_index,
+            event_index: event_index as i32,
+            height,
+            from_address: &transfer.from_address,
+            to_address: &transfer.to_address,
+            denom: &transfer.denom,
+            amount: transfer.amount.clone(),
+            from_dao_address: dao_address(&transfer.from_address),
+            to_dao_address: dao_address(&transfer.to_address),
+        })
+        .collect();
+    diesel::insert_into(crate::db::schema::bank_transfers::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    Ok(())
+}
+
+#[test]
+fn test_transfers_from_events() {
+    use crate::indexing::tx_events::TxEvent;
+    let event = |type_str: &str, attributes: &[(&str, &str)]| {
+        TxEvent::new(
+            type_str,
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    };
+    assert_eq!(
+        vec![
+            ("ujuno".to_string(), BigDecimal::from(10)),
+            ("ibc/27394FB0".to_string(), BigDecimal::from(5))
+        ],
+        parse_coins("10ujuno,5ibc/27394FB0").unwrap()
+    );
+    assert!(parse_coins("10").is_err());
+
+    let events = TxEvents::new(vec![
+        // Minted: received with nothing spent, no transfer.
+        event(
+            "coin_received",
+            &[("receiver", "juno1mint"), ("amount", "7ujuno")],
+        ),
+        // The fee.
+        event(
+            "transfer",
+            &[
+                ("recipient", "juno17xpfvakm2amg962yls6f84z3kell8c5lxtqmvp"),
+                ("sender", "juno1payer"),
+                ("amount", "2ujuno"),
+            ],
+        ),
+        event(
+            "transfer",
+            &[
+                ("recipient", "juno1member"),
+                ("sender", "juno1dao"),
+                ("amount", "10ujuno,5uatom"),
+            ],
+        ),
+        // Merged from a message log, with equal amounts.
+        event(
+            "transfer",
+            &[
+                ("recipient", "juno1payee"),
+                ("sender", "juno1other"),
+                ("amount", "3ujuno"),
+                ("recipient", "juno1member"),
+                ("sender", "juno1dao"),
+                ("amount", "3ujuno"),
+            ],
+        ),
+    ]);
+    assert_eq!(
+        vec![
+            BankTransfer::new("juno1dao", "juno1member", "ujuno", BigDecimal::from(10)),
+            BankTransfer::new("juno1dao", "juno1member", "uatom", BigDecimal::from(5)),
+            BankTransfer::new("juno1other", "juno1payee", "ujuno", BigDecimal::from(3)),
+            BankTransfer::new("juno1dao", "juno1member", "ujuno", BigDecimal::from(3)),
+        ],
+        transfers_from_events(&events).unwrap()
+    );
+    assert!(transfers_from_events(&TxEvents::new(vec![event(
+        "transfer",
+        &[("recipient", "juno1payee"), ("amount", "3ujuno")]
+    )]))
+    .is_err());
+}
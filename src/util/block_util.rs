@@ -0,0 +1,12 @@
+use crate::db::models::NewBlock;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use tendermint::block::Block;
+
+pub fn insert_block(db: &PgConnection, block_hash: &str, new_block: &Block) -> QueryResult<usize> {
+    use crate::db::schema::block::dsl::*;
+    diesel::insert_into(block)
+        .values(NewBlock::from_block_response(block_hash, new_block))
+        .on_conflict_do_nothing()
+        .execute(db)
+}
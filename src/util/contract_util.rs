@@ -1,4 +1,4 @@
-use crate::db::models::NewContract;
+use crate::db::models::{NewContract, NewContractHistory};
 use crate::indexing::event_map::EventMap;
 use anyhow::anyhow;
 use diesel::pg::PgConnection;
@@ -47,9 +47,84 @@ pub fn insert_contract(db: &PgConnection, contract_model: &NewContract) -> anyho
     use crate::db::schema::contracts::dsl::*;
     match diesel::insert_into(contracts)
         .values(contract_model)
+        .on_conflict_do_nothing()
         .execute(db)
     {
         Ok(_rows) => Ok(()),
         Err(e) => Err(anyhow!("Error: {:?}", e)),
     }
 }
+
+/// Records uploaded code. Storing the same code id again changes nothing.
+pub fn insert_code(
+    db: &PgConnection,
+    code_id: i64,
+    creator: &str,
+    checksum: &str,
+    height: i64,
+) -> anyhow::Result<()> {
+    use crate::db::schema::codes::dsl;
+    diesel::insert_into(dsl::codes)
+        .values((
+            dsl::code_id.eq(code_id),
+            dsl::creator.eq(creator),
+            dsl::checksum.eq(checksum),
+            dsl::height.eq(height),
+        ))
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    Ok(())
+}
+
+pub fn insert_contract_history(
+    db: &PgConnection,
+    entry: &NewContractHistory,
+) -> anyhow::Result<()> {
+    use crate::db::schema::contract_history::dsl::*;
+    diesel::insert_into(contract_history)
+        .values(entry)
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    Ok(())
+}
+
+/// Points an indexed contract at the code the latest entry of its history
+/// left it on, so replaying older blocks doesn't roll it back.
+pub fn update_contract_code_id(db: &PgConnection, address: &str) -> anyhow::Result<()> {
+    use crate::db::schema::contract_history::dsl as history;
+    use crate::db::schema::contracts::dsl;
+    let latest: Option<Option<i64>> = history::contract_history
+        .filter(history::contract_address.eq(address))
+        .filter(history::code_id.is_not_null())
+        .order((history::height.desc(), history::msg_index.desc()))
+        .select(history::code_id)
+        .first(db)
+        .optional()?;
+    if let Some(Some(code_id)) = latest {
+        diesel::update(dsl::contracts.filter(dsl::address.eq(address)))
+            .set(dsl::code_id.eq(code_id))
+            .execute(db)?;
+    }
+    Ok(())
+}
+
+/// Sets an indexed contract's admin to the one the latest entry of its
+/// history left it with, empty when it has none, so replaying older blocks
+/// doesn't roll it back.
+pub fn update_contract_admin(db: &PgConnection, address: &str) -> anyhow::Result<()> {
+    use crate::db::schema::contract_history::dsl as history;
+    use crate::db::schema::contracts::dsl;
+    let latest: Option<Option<String>> = history::contract_history
+        .filter(history::contract_address.eq(address))
+        .filter(history::operation.eq_any(vec!["instantiate", "update_admin", "clear_admin"]))
+        .order((history::height.desc(), history::msg_index.desc()))
+        .select(history::admin)
+        .first(db)
+        .optional()?;
+    if let Some(admin) = latest {
+        diesel::update(dsl::contracts.filter(dsl::address.eq(address)))
+            .set(dsl::admin.eq(admin.unwrap_or_default()))
+            .execute(db)?;
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use super::contract_util::ContractAddresses;
 use super::gov_token::{insert_gov_token, insert_gov_token25};
+use super::message_key::MessageKey;
 use crate::db::models::{Dao, NewDao, NewMultisig};
 use cw3_dao_2_5::msg::GovTokenMsg as GovTokenMsg25;
 
@@ -60,6 +61,7 @@ pub fn insert_dao_25(
     dao_image_url: Option<&String>,
     contract_addr: &ContractAddresses,
     height: Option<&BigDecimal>,
+    message_key: &MessageKey,
 ) -> anyhow::Result<()> {
     let mut gta_option = None;
     let gta: String;
@@ -70,7 +72,7 @@ pub fn insert_dao_25(
         gta = cw20_address.to_string();
         gta_option = Some(&gta);
     }
-    let _ = insert_gov_token25(db, gov_token, contract_addr, height)?;
+    let _ = insert_gov_token25(db, gov_token, contract_addr, height, message_key)?;
     insert_dao_private(
         db,
         dao_name,
@@ -90,6 +92,7 @@ pub fn insert_dao(
     dao_image_url: Option<&String>,
     contract_addr: &ContractAddresses,
     height: Option<&BigDecimal>,
+    message_key: &MessageKey,
 ) -> anyhow::Result<()> {
     let mut gta_option = None;
     let gta: String;
@@ -100,7 +103,7 @@ pub fn insert_dao(
         gta = cw20_address.to_string();
         gta_option = Some(&gta);
     }
-    let _ = insert_gov_token(db, gov_token, contract_addr, height)?;
+    let _ = insert_gov_token(db, gov_token, contract_addr, height, message_key)?;
     insert_dao_private(
         db,
         dao_name,
@@ -0,0 +1,329 @@
+use crate::db::models::NewDelegationLedgerEntry;
+use crate::indexing::tx_events::{TxEvent, TxEvents};
+use crate::util::bank_transfer::parse_coins;
+use crate::util::message_key::{tx_height, MessageKey};
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::collections::BTreeMap;
+
+pub const DELEGATE: &str = "delegate";
+pub const UNDELEGATE: &str = "undelegate";
+/// The source half of a redelegation.
+pub const REDELEGATE_OUT: &str = "redelegate_out";
+/// The destination half of a redelegation.
+pub const REDELEGATE_IN: &str = "redelegate_in";
+pub const WITHDRAW_REWARD: &str = "withdraw_reward";
+
+/// One coin of a change to a delegation, before it is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerEntry {
+    pub validator: String,
+    pub operation: &'static str,
+    pub denom: String,
+    pub amount: BigDecimal,
+    /// When undelegated or redelegated tokens are released, RFC 3339.
+    pub completion_time: Option<String>,
+}
+
+impl LedgerEntry {
+    pub fn new(validator: &str, operation: &'static str, denom: &str, amount: BigDecimal) -> Self {
+        LedgerEntry {
+            validator: validator.to_string(),
+            operation,
+            denom: denom.to_string(),
+            amount,
+            completion_time: None,
+        }
+    }
+}
+
+// Newer chains name the delegator in staking and distribution events.
+fn of_delegator(record: &TxEvent, delegator: &str) -> bool {
+    record
+        .attribute("delegator")
+        .map_or(true, |record_delegator| record_delegator == delegator)
+}
+
+// The one record of type `type_str` of `delegator` that `matches`, if any.
+// Several of them, left by messages of a transaction that events couldn't
+// be split up for, can't be told apart.
+fn delegator_record(
+    events: &TxEvents,
+    type_str: &str,
+    delegator: &str,
+    matches: impl Fn(&TxEvent) -> bool,
+) -> anyhow::Result<Option<TxEvent>> {
+    let mut records = events
+        .records(type_str)
+        .into_iter()
+        .filter(|record| of_delegator(record, delegator) && matches(record));
+    match (records.next(), records.next()) {
+        (Some(_), Some(_)) => Err(anyhow!(
+            "several {} events of {} match the message",
+            type_str,
+            delegator
+        )),
+        (record, _) => Ok(record),
+    }
+}
+
+/// The rewards `delegator` withdrew from `validators` in `events`. Besides
+/// explicit withdrawals, any change to a delegation withdraws its rewards.
+pub fn reward_entries(
+    events: &TxEvents,
+    delegator: &str,
+    validators: &[&str],
+) -> anyhow::Result<Vec<LedgerEntry>> {
+    let mut entries = vec![];
+    for validator in validators {
+        let record = delegator_record(events, "withdraw_rewards", delegator, |record| {
+            record.attribute("validator") == Some(*validator)
+        })?;
+        if let Some(record) = record {
+            // Empty when there was nothing to withdraw.
+            for (denom, amount) in parse_coins(record.attribute("amount").unwrap_or_default())? {
+                entries.push(LedgerEntry::new(validator, WITHDRAW_REWARD, &denom, amount));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// When the tokens `delegator` undelegated or redelegated, as `type_str`
+/// ("unbond" or "redelegate") events of `validators` (attribute, address)
+/// record it, are released.
+pub fn completion_time(
+    events: &TxEvents,
+    type_str: &str,
+    delegator: &str,
+    validators: &[(&str, &str)],
+    denom: &str,
+    amount: &BigDecimal,
+) -> anyhow::Result<Option<String>> {
+    let record = delegator_record(events, type_str, delegator, |record| {
+        validators
+            .iter()
+            .all(|(key, validator)| record.attribute(key) == Some(*validator))
+            // Older chains leave the denom out of the amount.
+            && record.attribute("amount").map_or(true, |record_amount| {
+                record_amount == amount.to_string()
+                    || record_amount == format!("{}{}", amount, denom)
+            })
+    })?;
+    Ok(record.and_then(|record| record.attribute("completion_time").map(String::from)))
+}
+
+pub fn insert_ledger_entries(
+    db: &PgConnection,
+    events: &TxEvents,
+    delegator: &str,
+    entries: &[LedgerEntry],
+) -> anyhow::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let height = tx_height(events)?;
+    let message_key = MessageKey::from_events(events, 0);
+    let rows: Vec<NewDelegationLedgerEntry> = entries
+        .iter()
+        .enumerate()
+        .map(|(event_index, entry)| NewDelegationLedgerEntry {
+            delegator,
+            validator: &entry.validator,
+            operation: entry.operation,
+            denom: &entry.denom,
+            amount: entry.amount.clone(),
+            completion_time: entry.completion_time.as_deref(),
+            height,
+            tx_hash: message_key.tx_hash.clone(),
+            msg_index: message_key.msg_index,
+            event_index: event_index as i32,
+        })
+        .collect();
+    diesel::insert_into(crate::db::schema::delegation_ledger::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(db)?;
+    Ok(())
+}
+
+/// What a delegator has delegated to a validator, in one denom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelegationBalance {
+    pub validator: String,
+    pub denom: String,
+    pub amount: BigDecimal,
+}
+
+/// Sums (validator, operation, denom, amount) ledger rows into what is
+/// still delegated, leaving out delegations that were fully withdrawn. The
+/// same sum as the `delegation_balance` view.
+pub fn sum_balances(
+    rows: impl IntoIterator<Item = (String, String, String, BigDecimal)>,
+) -> Vec<DelegationBalance> {
+    let mut balances: BTreeMap<(String, String), BigDecimal> = BTreeMap::new();
+    for (validator, operation, denom, amount) in rows {
+        let change = match operation.as_str() {
+            DELEGATE | REDELEGATE_IN => amount,
+            UNDELEGATE | REDELEGATE_OUT => -amount,
+            _ => continue,
+        };
+        let balance = balances.entry((validator, denom)).or_default();
+        *balance = balance.clone() + change;
+    }
+    balances
+        .into_iter()
+        .filter(|(_, amount)| *amount != BigDecimal::default())
+        .map(|((validator, denom), amount)| DelegationBalance {
+            validator,
+            denom,
+            amount,
+        })
+        .collect()
+}
+
+/// What `delegator`, a DAO treasury say, currently has delegated, by
+/// validator.
+pub fn delegated_balances(
+    db: &PgConnection,
+    delegator_address: &str,
+) -> anyhow::Result<Vec<DelegationBalance>> {
+    use crate::db::schema::delegation_ledger::dsl::*;
+    let rows: Vec<(String, String, String, BigDecimal)> = delegation_ledger
+        .select((validator, operation, denom, amount))
+        .filter(delegator.eq(delegator_address))
+        .load(db)?;
+    Ok(sum_balances(rows))
+}
+
+#[test]
+fn test_sum_balances() {
+    let row = |validator: &str, operation: &str, amount: i64| {
+        (
+            validator.to_string(),
+            operation.to_string(),
+            "ujuno".to_string(),
+            BigDecimal::from(amount),
+        )
+    };
+    let balances = sum_balances(vec![
+        row("junovaloper1a", DELEGATE, 100),
+        row("junovaloper1b", DELEGATE, 50),
+        row("junovaloper1a", WITHDRAW_REWARD, 7),
+        row("junovaloper1a", REDELEGATE_OUT, 30),
+        row("junovaloper1c", REDELEGATE_IN, 30),
+        row("junovaloper1b", UNDELEGATE, 50),
+    ]);
+    assert_eq!(
+        vec![
+            DelegationBalance {
+                validator: "junovaloper1a".to_string(),
+                denom: "ujuno".to_string(),
+                amount: BigDecimal::from(70),
+            },
+            DelegationBalance {
+                validator: "junovaloper1c".to_string(),
+                denom: "ujuno".to_string(),
+                amount: BigDecimal::from(30),
+            },
+        ],
+        balances
+    );
+}
+
+#[test]
+fn test_matching_events() {
+    let event = |type_str: &str, attributes: &[(&str, &str)]| {
+        TxEvent::new(
+            type_str,
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    };
+    let events = TxEvents::new(vec![
+        event(
+            "withdraw_rewards",
+            &[("amount", "3ujuno"), ("validator", "junovaloper1a")],
+        ),
+        event(
+            "withdraw_rewards",
+            &[
+                ("amount", "4ujuno"),
+                ("validator", "junovaloper1b"),
+                ("delegator", "juno1dao"),
+            ],
+        ),
+        event(
+            "withdraw_rewards",
+            &[
+                ("amount", "5ujuno"),
+                ("validator", "junovaloper1b"),
+                ("delegator", "juno1other"),
+            ],
+        ),
+        event(
+            "unbond",
+            &[
+                ("validator", "junovaloper1a"),
+                ("amount", "10"),
+                ("completion_time", "2022-07-01T00:00:00Z"),
+                ("validator", "junovaloper1a"),
+                ("amount", "20"),
+                ("completion_time", "2022-07-02T00:00:00Z"),
+            ],
+        ),
+    ]);
+    // Only the rewards of the message's own delegations.
+    assert_eq!(
+        vec![LedgerEntry::new(
+            "junovaloper1b",
+            WITHDRAW_REWARD,
+            "ujuno",
+            BigDecimal::from(4)
+        )],
+        reward_entries(&events, "juno1dao", &["junovaloper1b"]).unwrap()
+    );
+    assert_eq!(
+        2,
+        reward_entries(&events, "juno1dao", &["junovaloper1a", "junovaloper1b"])
+            .unwrap()
+            .len()
+    );
+    assert_eq!(
+        Some("2022-07-02T00:00:00Z".to_string()),
+        completion_time(
+            &events,
+            "unbond",
+            "juno1dao",
+            &[("validator", "junovaloper1a")],
+            "ujuno",
+            &BigDecimal::from(20)
+        )
+        .unwrap()
+    );
+
+    // Two undelegations of the same amount can't be told apart.
+    let twice = TxEvents::new(vec![
+        event(
+            "unbond",
+            &[("validator", "junovaloper1a"), ("amount", "10ujuno")],
+        ),
+        event(
+            "unbond",
+            &[("validator", "junovaloper1a"), ("amount", "10ujuno")],
+        ),
+    ]);
+    assert!(completion_time(
+        &twice,
+        "unbond",
+        "juno1dao",
+        &[("validator", "junovaloper1a")],
+        "ujuno",
+        &BigDecimal::from(10)
+    )
+    .is_err());
+}
@@ -0,0 +1,24 @@
+use super::height_ranges::HeightRange;
+use super::query_stream::TxSearchRequest;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+/// Records a tx_search request for `range` that exhausted its retry budget,
+/// so the failure outlives the sync that hit it.
+pub fn record_failed_request(
+    db: &PgConnection,
+    range: &HeightRange,
+    request: &TxSearchRequest,
+) -> QueryResult<usize> {
+    use crate::db::schema::failed_tx_search::dsl::*;
+    diesel::insert_into(failed_tx_search)
+        .values((
+            start_height.eq(range.start as i64),
+            end_height.eq(range.end as i64),
+            query.eq(request.query.to_string()),
+            page.eq(request.page as i32),
+            attempts.eq(request.failures as i32),
+            error.eq(request.last_error.as_ref()),
+        ))
+        .execute(db)
+}
@@ -1,6 +1,7 @@
 use super::contract_util::ContractAddresses;
 use super::dao::get_dao;
 use super::insert_marketing_info::insert_marketing_info;
+use super::message_key::MessageKey;
 use super::update_balance::update_balance;
 use crate::{
     db::models::{Cw20, NewGovToken},
@@ -15,7 +16,7 @@ use cw20_011_1::Cw20Coin as Cw20Coin_11_1;
 use cw3_dao::msg::{GovTokenInstantiateMsg, GovTokenMsg};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use log::{error, warn};
+use log::{debug, error, warn};
 use serde_json::Value;
 
 pub fn cw20_coin_from_value(value_dict: &Value) -> Option<Cw20Coin_11_1> {
@@ -159,8 +160,15 @@ pub fn insert_gov_token25(
     token_msg: &GovTokenMsg25,
     contract_addresses: &ContractAddresses,
     height: Option<&BigDecimal>,
+    message_key: &MessageKey,
 ) -> QueryResult<i32> {
-    insert_gov_token(db, &convert_2_5_to_3(token_msg), contract_addresses, height)
+    insert_gov_token(
+        db,
+        &convert_2_5_to_3(token_msg),
+        contract_addresses,
+        height,
+        message_key,
+    )
 }
 
 pub fn insert_gov_token(
@@ -168,6 +176,7 @@ pub fn insert_gov_token(
     token_msg: &GovTokenMsg,
     contract_addresses: &ContractAddresses,
     height: Option<&BigDecimal>,
+    message_key: &MessageKey,
 ) -> QueryResult<i32> {
     use crate::db::schema::gov_token::dsl::*;
     match token_msg {
@@ -176,8 +185,18 @@ pub fn insert_gov_token(
             initial_dao_balance,
             ..
         } => {
+            let cw20_address = contract_addresses.cw20_address.as_ref().unwrap();
+            let existing_token = gov_token
+                .filter(address.eq(cw20_address))
+                .select(address)
+                .first::<String>(db as &PgConnection)
+                .optional()?;
             let mut marketing_record_id: Option<i32> = None;
-            if let Some(marketing) = &msg.marketing {
+            // Only record marketing info the first time we see the token, so
+            // re-indexing the instantiation doesn't add duplicate rows.
+            if existing_token.is_some() {
+                debug!("gov token {} already indexed", cw20_address);
+            } else if let Some(marketing) = &msg.marketing {
                 marketing_record_id = Some(
                     insert_marketing_info(
                         db,
@@ -188,10 +207,10 @@ pub fn insert_gov_token(
                     .unwrap(),
                 );
             }
-            let cw20_address = contract_addresses.cw20_address.as_ref().unwrap();
             let token_model = NewGovToken::from_msg(cw20_address, marketing_record_id, msg);
             let _ = diesel::insert_into(gov_token)
                 .values(token_model)
+                .on_conflict_do_nothing()
                 .execute(db as &PgConnection);
             let dao_address = contract_addresses.contract_address.as_ref().unwrap();
             let amount = if let Some(balance) = initial_dao_balance {
@@ -206,12 +225,13 @@ pub fn insert_gov_token(
                 dao_address,
                 dao_address, // As the minter the DAO is also the sender for its own initial balance (???)
                 u128::from(amount),
+                &message_key.with_event_index(0),
             );
             if let Err(e) = initial_update_result {
                 error!("error updating initial balance {}", e);
             } else {
                 // This handles the initial token distributions but not the treasury.
-                for balance in &msg.initial_balances {
+                for (i, balance) in msg.initial_balances.iter().enumerate() {
                     let amount = balance.amount;
                     let recipient = &balance.address;
                     if let Err(e) = update_balance(
@@ -221,6 +241,7 @@ pub fn insert_gov_token(
                         dao_address,
                         recipient,
                         u128::from(amount),
+                        &message_key.with_event_index(i + 1),
                     ) {
                         error!("Error updating balance {:?}", e);
                     }
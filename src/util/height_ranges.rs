@@ -0,0 +1,118 @@
+use std::fmt;
+
+/// An inclusive range of block heights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HeightRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl HeightRange {
+    pub fn new(start: u64, end: u64) -> Self {
+        HeightRange { start, end }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Splits this range into consecutive ranges of at most `max_len` blocks.
+    pub fn split(&self, max_len: u64) -> Vec<HeightRange> {
+        let max_len = max_len.max(1);
+        let mut ranges = vec![];
+        let mut start = self.start;
+        while start <= self.end {
+            let end = self.end.min(start + max_len - 1);
+            ranges.push(HeightRange::new(start, end));
+            start = end + 1;
+        }
+        ranges
+    }
+}
+
+impl fmt::Display for HeightRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Returns the ranges between `first` and `last` (inclusive) that are not
+/// covered by any of the `covered` ranges, which may overlap and be unsorted.
+pub fn missing_ranges(first: u64, last: u64, covered: &[HeightRange]) -> Vec<HeightRange> {
+    let mut covered = covered.to_vec();
+    covered.sort();
+    let mut missing = vec![];
+    let mut next_height = first;
+    for range in covered {
+        if next_height > last {
+            break;
+        }
+        if range.end < next_height {
+            continue;
+        }
+        if range.start > next_height {
+            missing.push(HeightRange::new(next_height, last.min(range.start - 1)));
+        }
+        next_height = next_height.max(range.end + 1);
+    }
+    if next_height <= last {
+        missing.push(HeightRange::new(next_height, last));
+    }
+    missing
+}
+
+/// Merges overlapping and adjacent ranges.
+pub fn merge_ranges(ranges: &[HeightRange]) -> Vec<HeightRange> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort();
+    let mut merged: Vec<HeightRange> = vec![];
+    for range in sorted {
+        if let Some(previous) = merged.last_mut() {
+            if range.start <= previous.end + 1 {
+                previous.end = previous.end.max(range.end);
+                continue;
+            }
+        }
+        merged.push(range);
+    }
+    merged
+}
+
+#[test]
+fn test_missing_ranges() {
+    let covered = vec![
+        HeightRange::new(150, 200),
+        HeightRange::new(100, 120),
+        HeightRange::new(200, 250),
+        HeightRange::new(400, 500),
+    ];
+    assert_eq!(
+        vec![
+            HeightRange::new(121, 149),
+            HeightRange::new(251, 399),
+            HeightRange::new(501, 600),
+        ],
+        missing_ranges(100, 600, &covered)
+    );
+    assert_eq!(vec![HeightRange::new(1, 10)], missing_ranges(1, 10, &[]));
+    assert!(missing_ranges(110, 115, &covered).is_empty());
+}
+
+#[test]
+fn test_split_and_merge_ranges() {
+    let range = HeightRange::new(1, 250);
+    let split = range.split(100);
+    assert_eq!(
+        vec![
+            HeightRange::new(1, 100),
+            HeightRange::new(101, 200),
+            HeightRange::new(201, 250),
+        ],
+        split
+    );
+    assert_eq!(vec![range], merge_ranges(&split));
+}
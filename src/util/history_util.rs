@@ -16,3 +16,12 @@ pub fn tx_to_hash(tx: &Transaction) -> tendermint::abci::transaction::Hash {
 
     tendermint::abci::transaction::Hash::new(hash_bytes)
 }
+
+/// The Tendermint hash of a transaction as it appears in a block: the
+/// SHA-256 of its raw bytes.
+pub fn tx_bytes_hash(tx: &Transaction) -> tendermint::abci::transaction::Hash {
+    let digest = Sha256::digest(tx.as_bytes());
+    let mut hash_bytes = [0u8; HASH_LENGTH];
+    hash_bytes.copy_from_slice(&digest);
+    tendermint::abci::transaction::Hash::new(hash_bytes)
+}
@@ -0,0 +1,131 @@
+use anyhow::anyhow;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+pub const OUTGOING: &str = "outgoing";
+pub const INCOMING: &str = "incoming";
+
+pub const PENDING: &str = "pending";
+pub const ACKNOWLEDGED: &str = "acknowledged";
+/// Acknowledged with an error; the sender was refunded.
+pub const FAILED: &str = "failed";
+pub const TIMED_OUT: &str = "timed_out";
+pub const RECEIVED: &str = "received";
+pub const RECEIVE_FAILED: &str = "receive_failed";
+
+/// The data of an ICS-20 transfer packet.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct FungibleTokenPacketData {
+    /// The full denom trace on the sending chain.
+    pub denom: String,
+    pub amount: String,
+    pub sender: String,
+    pub receiver: String,
+}
+
+impl FungibleTokenPacketData {
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// A denom trace split into the port/channel hops the tokens took and the
+/// denom they started as, which may have slashes of its own.
+pub fn split_denom_trace(denom_trace: &str) -> (String, String) {
+    let parts: Vec<&str> = denom_trace.split('/').collect();
+    let mut hops = 0;
+    while parts.len() > hops * 2 + 2 && parts[hops * 2 + 1].starts_with("channel-") {
+        hops += 1;
+    }
+    (parts[..hops * 2].join("/"), parts[hops * 2..].join("/"))
+}
+
+/// The denom a trace has on the chain at the end of it: the base denom for
+/// native tokens, otherwise ibc/ and the hash of the trace.
+pub fn ibc_denom(denom_trace: &str) -> String {
+    let (path, base_denom) = split_denom_trace(denom_trace);
+    if path.is_empty() {
+        return base_denom;
+    }
+    format!("ibc/{:X}", Sha256::digest(denom_trace.as_bytes()))
+}
+
+/// The trace tokens in a received packet have on this chain. Tokens going
+/// back where they came from lose the hop they left by; any others gain
+/// the hop they arrived by.
+pub fn received_denom_trace(
+    source_port: &str,
+    source_channel: &str,
+    destination_port: &str,
+    destination_channel: &str,
+    packet_denom: &str,
+) -> String {
+    let source_prefix = format!("{}/{}/", source_port, source_channel);
+    match packet_denom.strip_prefix(&source_prefix) {
+        Some(unwound) => unwound.to_string(),
+        None => format!(
+            "{}/{}/{}",
+            destination_port, destination_channel, packet_denom
+        ),
+    }
+}
+
+/// The error in an ICS-20 acknowledgement, or None if it acknowledges
+/// success.
+pub fn acknowledgement_error(acknowledgement: &[u8]) -> anyhow::Result<Option<String>> {
+    let acknowledgement: serde_json::Value = serde_json::from_slice(acknowledgement)?;
+    if acknowledgement.get("result").is_some() {
+        return Ok(None);
+    }
+    match acknowledgement.get("error") {
+        Some(error) => Ok(Some(error.as_str().unwrap_or_default().to_string())),
+        None => Err(anyhow!("unrecognized acknowledgement {}", acknowledgement)),
+    }
+}
+
+#[test]
+fn test_denom_traces() {
+    assert_eq!(
+        ("".to_string(), "ujuno".to_string()),
+        split_denom_trace("ujuno")
+    );
+    assert_eq!(
+        (
+            "transfer/channel-0/transfer/channel-141".to_string(),
+            "gamm/pool/1".to_string()
+        ),
+        split_denom_trace("transfer/channel-0/transfer/channel-141/gamm/pool/1")
+    );
+    assert_eq!("ujuno", ibc_denom("ujuno"));
+    assert_eq!(
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2",
+        ibc_denom("transfer/channel-0/uatom")
+    );
+
+    // Atom arriving from the hub.
+    assert_eq!(
+        "transfer/channel-1/uatom",
+        received_denom_trace("transfer", "channel-207", "transfer", "channel-1", "uatom")
+    );
+    // Juno coming back from the hub.
+    assert_eq!(
+        "ujuno",
+        received_denom_trace(
+            "transfer",
+            "channel-207",
+            "transfer",
+            "channel-1",
+            "transfer/channel-207/ujuno"
+        )
+    );
+
+    assert_eq!(
+        None,
+        acknowledgement_error(br#"{"result":"AQ=="}"#).unwrap()
+    );
+    assert_eq!(
+        Some("insufficient funds".to_string()),
+        acknowledgement_error(br#"{"error":"insufficient funds"}"#).unwrap()
+    );
+    assert!(acknowledgement_error(b"{}").is_err());
+}
@@ -0,0 +1,123 @@
+use crate::indexing::event_map::EventMap;
+use anyhow::anyhow;
+use log::warn;
+
+/// Event map key for the hash of the transaction being indexed.
+pub const TX_HASH_KEY: &str = "tx.hash";
+
+/// Event map key for the height of the block the transaction being indexed
+/// is in.
+pub const TX_HEIGHT_KEY: &str = "tx.height";
+
+/// Event map key for the result code of the transaction being indexed.
+/// Anything other than "0" means the transaction failed.
+pub const TX_CODE_KEY: &str = "tx.code";
+
+/// Event map key for the position of the message being indexed within its
+/// transaction. Messages wrapped in another, like those an authz `MsgExec`
+/// executes, are numbered after the transaction's own messages.
+pub const MSG_INDEX_KEY: &str = "tx.msg_index";
+
+/// Event map key for the account that executed a message for its signer,
+/// such as the grantee of the authz `MsgExec` the message was wrapped in.
+pub const EXECUTOR_KEY: &str = "tx.executor";
+
+/// Natural key for a row written while indexing a transaction: the
+/// transaction hash, the index of the message within the transaction and
+/// the index of the event (transfer, balance change...) within the message.
+/// Writes keyed this way can be replayed without duplicating rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageKey {
+    /// None when the transaction hash isn't known; such rows can't be
+    /// de-duplicated.
+    pub tx_hash: Option<String>,
+    pub msg_index: i32,
+    pub event_index: i32,
+}
+
+impl MessageKey {
+    pub fn new(tx_hash: Option<String>, msg_index: i32, event_index: i32) -> Self {
+        MessageKey {
+            tx_hash,
+            msg_index,
+            event_index,
+        }
+    }
+
+    /// Reads the transaction hash and message index from `events`.
+    pub fn from_events(events: &EventMap, event_index: usize) -> Self {
+        let tx_hash = events
+            .get(TX_HASH_KEY)
+            .and_then(|hashes| hashes.first())
+            .cloned();
+        if tx_hash.is_none() {
+            warn!(
+                "No {} in events, writes will not be idempotent",
+                TX_HASH_KEY
+            );
+        }
+        let msg_index = events
+            .get(MSG_INDEX_KEY)
+            .and_then(|indices| indices.first())
+            .and_then(|index| index.parse::<i32>().ok())
+            .unwrap_or_default();
+        MessageKey::new(tx_hash, msg_index, event_index as i32)
+    }
+
+    /// The same message with a different event index.
+    pub fn with_event_index(&self, event_index: usize) -> Self {
+        MessageKey::new(self.tx_hash.clone(), self.msg_index, event_index as i32)
+    }
+}
+
+/// Whether the transaction `events` came from failed. Events without a
+/// result code are taken to be from a successful transaction.
+pub fn tx_failed(events: &EventMap) -> bool {
+    events
+        .get(TX_CODE_KEY)
+        .and_then(|codes| codes.first())
+        .map(|code| code != "0")
+        .unwrap_or(false)
+}
+
+/// The height of the block the transaction `events` came from.
+pub fn tx_height(events: &EventMap) -> anyhow::Result<i64> {
+    let height = events
+        .get(TX_HEIGHT_KEY)
+        .and_then(|heights| heights.first())
+        .ok_or_else(|| anyhow!("no {} in events", TX_HEIGHT_KEY))?;
+    Ok(height.parse()?)
+}
+
+/// Who executed the message `events` are for on its signer's behalf, if
+/// anyone. For messages wrapped more than once this is the innermost
+/// wrapper's executor.
+pub fn executor(events: &EventMap) -> Option<&str> {
+    events
+        .get(EXECUTOR_KEY)
+        .and_then(|executors| executors.last())
+        .map(|executor| executor.as_str())
+}
+
+#[test]
+fn test_message_key_from_events() {
+    let mut events = EventMap::new();
+    events.insert(TX_HASH_KEY.to_string(), vec!["ABCDEF".to_string()]);
+    events.insert(MSG_INDEX_KEY.to_string(), vec!["2".to_string()]);
+    let key = MessageKey::from_events(&events, 3);
+    assert_eq!(MessageKey::new(Some("ABCDEF".to_string()), 2, 3), key);
+    assert_eq!(1, key.with_event_index(1).event_index);
+    assert_eq!(None, MessageKey::from_events(&EventMap::new(), 0).tx_hash);
+    assert!(tx_height(&events).is_err());
+    events.insert(TX_HEIGHT_KEY.to_string(), vec!["42".to_string()]);
+    assert_eq!(42, tx_height(&events).unwrap());
+    assert!(!tx_failed(&events));
+    events.insert(TX_CODE_KEY.to_string(), vec!["5".to_string()]);
+    assert!(tx_failed(&events));
+    assert_eq!(None, executor(&events));
+    events.insert(
+        EXECUTOR_KEY.to_string(),
+        vec!["juno1outer".to_string(), "juno1inner".to_string()],
+    );
+    assert_eq!(Some("juno1inner"), executor(&events));
+}
@@ -1,14 +1,20 @@
+use crate::metrics::QUERY_REQUEUES;
 use async_std::stream::Stream;
 use async_std::task::{Context, Poll};
+use rand::Rng;
 use std::collections::VecDeque;
 use std::fmt::{Debug, Formatter};
 use std::pin::Pin;
+use std::time::Duration;
 use tendermint_rpc::query::Query;
 
 pub struct TxSearchRequest {
     pub query: Query,
     pub page: u32,
     pub reque_count: i64,
+    /// How many times this request has failed with an error.
+    pub failures: u32,
+    pub last_error: Option<String>,
 }
 
 impl Debug for TxSearchRequest {
@@ -16,10 +22,24 @@ impl Debug for TxSearchRequest {
         f.debug_struct("TxSearchRequest")
             .field("page", &self.page)
             .field("reque_count", &self.reque_count)
+            .field("failures", &self.failures)
+            .field("last_error", &self.last_error)
             .finish()
     }
 }
 
+/// Exponential backoff delay for the `attempt`th retry (starting at 1):
+/// `base_ms` doubled for every previous attempt, capped at `max_ms`.
+/// `jitter` (0.0 to 1.0) picks a delay between half and all of that, so
+/// requests that failed together don't all retry together.
+pub fn backoff_delay(base_ms: u64, max_ms: u64, attempt: u32, jitter: f64) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(32);
+    let delay_ms = base_ms.saturating_mul(1u64 << exponent).min(max_ms);
+    let half_delay_ms = delay_ms / 2;
+    let jitter_ms = ((delay_ms - half_delay_ms) as f64 * jitter.clamp(0.0, 1.0)) as u64;
+    Duration::from_millis(half_delay_ms + jitter_ms)
+}
+
 pub trait TxHelper {
     fn transactions_for_heights(start: u64, stop: u64) -> Query;
 }
@@ -43,8 +63,23 @@ impl TxSearchRequest {
             query,
             page,
             reque_count: -1,
+            failures: 0,
+            last_error: None,
         }
     }
+
+    /// Records a failed attempt. Returns false once the request has failed
+    /// more than `max_retries` times and shouldn't be retried.
+    pub fn record_failure(&mut self, error: String, max_retries: u32) -> bool {
+        self.failures += 1;
+        self.last_error = Some(error);
+        self.failures <= max_retries
+    }
+
+    /// How long to wait before retrying this request.
+    pub fn retry_delay(&self, base_ms: u64, max_ms: u64) -> Duration {
+        backoff_delay(base_ms, max_ms, self.failures, rand::thread_rng().gen())
+    }
 }
 
 impl Default for TxSearchRequest {
@@ -55,22 +90,39 @@ impl Default for TxSearchRequest {
 
 pub struct QueryStream {
     queue: VecDeque<Box<TxSearchRequest>>,
+    failed: Vec<Box<TxSearchRequest>>,
 }
 
 impl QueryStream {
     pub fn new() -> Self {
         let queue = VecDeque::new();
-        QueryStream { queue }
+        QueryStream {
+            queue,
+            failed: vec![],
+        }
     }
 
     pub fn enqueue(&mut self, mut request: Box<TxSearchRequest>) {
         request.reque_count += 1;
+        if request.reque_count > 0 {
+            QUERY_REQUEUES.inc();
+        }
         self.queue.push_back(request)
     }
 
     pub fn is_empty(&self) -> bool {
         self.queue.is_empty()
     }
+
+    /// Sets aside a request that exhausted its retry budget.
+    pub fn fail(&mut self, request: Box<TxSearchRequest>) {
+        self.failed.push(request)
+    }
+
+    /// Removes and returns the requests that exhausted their retry budget.
+    pub fn take_failed(&mut self) -> Vec<Box<TxSearchRequest>> {
+        std::mem::take(&mut self.failed)
+    }
 }
 
 impl Default for QueryStream {
@@ -125,3 +177,40 @@ async fn test_query_stream() {
         assert_eq!(1, tx_request.reque_count, "Expected re-queue count not set");
     }
 }
+
+#[test]
+fn test_retry_budget_and_backoff() {
+    assert_eq!(
+        Duration::from_millis(50),
+        backoff_delay(100, 10_000, 1, 0.0)
+    );
+    assert_eq!(
+        Duration::from_millis(100),
+        backoff_delay(100, 10_000, 1, 1.0)
+    );
+    assert_eq!(
+        Duration::from_millis(800),
+        backoff_delay(100, 10_000, 4, 1.0)
+    );
+    assert_eq!(
+        Duration::from_millis(10_000),
+        backoff_delay(100, 10_000, 20, 1.0)
+    );
+    assert_eq!(
+        Duration::from_millis(10_000),
+        backoff_delay(100, 10_000, 200, 1.0)
+    );
+
+    let mut qs = QueryStream::default();
+    let mut request = Box::new(TxSearchRequest::default());
+    assert!(request.record_failure("timeout".to_string(), 2));
+    assert!(request.record_failure("timeout".to_string(), 2));
+    assert!(!request.record_failure("timeout".to_string(), 2));
+    assert!(request.retry_delay(100, 10_000) <= Duration::from_millis(400));
+    qs.fail(request);
+    assert!(qs.is_empty());
+    let failed = qs.take_failed();
+    assert_eq!(1, failed.len());
+    assert_eq!(Some("timeout".to_string()), failed[0].last_error);
+    assert!(qs.take_failed().is_empty());
+}
@@ -0,0 +1,134 @@
+use super::height_ranges::HeightRange;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use std::collections::BTreeMap;
+
+/// Name of the cursor written by the historical `block_synchronizer`.
+pub const HISTORICAL_SYNC: &str = "historical";
+
+/// Tracks block ranges as they finish indexing (in any order) and keeps
+/// track of the highest height below which every range has finished.
+#[derive(Debug)]
+pub struct SyncCursor {
+    height: u64,
+    // start -> end of finished ranges that are not yet contiguous with `height`
+    completed: BTreeMap<u64, u64>,
+}
+
+impl SyncCursor {
+    /// Creates a cursor where everything up to and including `height` is
+    /// considered indexed.
+    pub fn new(height: u64) -> Self {
+        SyncCursor {
+            height,
+            completed: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a cursor for a sync that starts at `start_height`.
+    pub fn starting_at(start_height: u64) -> Self {
+        SyncCursor::new(start_height.saturating_sub(1))
+    }
+
+    /// The highest contiguous height whose transactions were all indexed.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Marks the inclusive range `start..=end` as indexed. Returns true if
+    /// this advanced the contiguous height.
+    pub fn complete(&mut self, start: u64, end: u64) -> bool {
+        if end <= self.height {
+            return false;
+        }
+        let range_end = self.completed.entry(start).or_insert(end);
+        if *range_end < end {
+            *range_end = end;
+        }
+        let previous_height = self.height;
+        while let Some((&range_start, &range_end)) = self.completed.iter().next() {
+            if range_start > self.height + 1 {
+                break;
+            }
+            self.completed.remove(&range_start);
+            if range_end > self.height {
+                self.height = range_end;
+            }
+        }
+        self.height != previous_height
+    }
+}
+
+pub fn get_sync_height(db: &PgConnection, sync_name: &str) -> QueryResult<Option<u64>> {
+    use crate::db::schema::sync_state::dsl::*;
+    let sync_height = sync_state
+        .filter(name.eq(sync_name))
+        .select(height)
+        .first::<i64>(db)
+        .optional()?;
+    Ok(sync_height.map(|value| value as u64))
+}
+
+sql_function!(fn greatest(a: BigInt, b: BigInt) -> BigInt);
+
+/// Moves the cursor `sync_name` up to `sync_height`. It is never moved back,
+/// so a sync over ranges behind it, such as a backfill, leaves it alone.
+pub fn set_sync_height(db: &PgConnection, sync_name: &str, sync_height: u64) -> QueryResult<usize> {
+    use crate::db::schema::sync_state::dsl::*;
+    use diesel::pg::upsert::excluded;
+    diesel::insert_into(sync_state)
+        .values((name.eq(sync_name), height.eq(sync_height as i64)))
+        .on_conflict(name)
+        .do_update()
+        .set((
+            height.eq(greatest(height, excluded(height))),
+            updated_at.eq(diesel::dsl::now),
+        ))
+        .execute(db)
+}
+
+/// Records that every transaction in `range` has been indexed.
+pub fn record_indexed_range(db: &PgConnection, range: &HeightRange) -> QueryResult<usize> {
+    use crate::db::schema::indexed_block_range::dsl::*;
+    diesel::insert_into(indexed_block_range)
+        .values((
+            start_height.eq(range.start as i64),
+            end_height.eq(range.end as i64),
+        ))
+        .on_conflict_do_nothing()
+        .execute(db)
+}
+
+/// All recorded indexed ranges that overlap `first..=last`.
+pub fn get_indexed_ranges(
+    db: &PgConnection,
+    first: u64,
+    last: u64,
+) -> QueryResult<Vec<HeightRange>> {
+    use crate::db::schema::indexed_block_range::dsl::*;
+    let rows = indexed_block_range
+        .filter(end_height.ge(first as i64))
+        .filter(start_height.le(last as i64))
+        .select((start_height, end_height))
+        .load::<(i64, i64)>(db)?;
+    Ok(rows
+        .into_iter()
+        .map(|(start, end)| HeightRange::new(start as u64, end as u64))
+        .collect())
+}
+
+#[test]
+fn test_sync_cursor_out_of_order() {
+    let mut cursor = SyncCursor::starting_at(100);
+    assert_eq!(99, cursor.height());
+    assert!(!cursor.complete(201, 300));
+    assert_eq!(99, cursor.height());
+    assert!(cursor.complete(100, 200));
+    assert_eq!(300, cursor.height());
+    // Ranges already below the cursor don't move it.
+    assert!(!cursor.complete(150, 250));
+    // Overlapping ranges (as produced by block_synchronizer) still advance.
+    assert!(cursor.complete(300, 400));
+    assert_eq!(400, cursor.height());
+}
@@ -8,6 +8,8 @@ use crate::config::IndexerConfig;
 use crate::db::models::{NewTransaction, Transaction};
 use crate::db::schema::transaction::dsl::*;
 use crate::indexing::indexer_registry::IndexerRegistry;
+use crate::source::SourceTx;
+use crate::util::height_ranges::HeightRange;
 
 pub fn insert_transaction(
     tx_response: &Response,
@@ -25,6 +27,7 @@ pub fn insert_transaction(
 
         diesel::insert_into(transaction)
             .values(new_transaction)
+            .on_conflict_do_nothing()
             .execute(database_connection)?;
         Ok(())
     } else {
@@ -32,6 +35,26 @@ pub fn insert_transaction(
     }
 }
 
+/// Records a transaction the chain rejected, with why.
+pub fn insert_failed_transaction(
+    db_connection: &PgConnection,
+    source_tx: &SourceTx,
+) -> anyhow::Result<()> {
+    use crate::db::schema::failed_transaction::dsl as failed;
+    diesel::insert_into(failed::failed_transaction)
+        .values((
+            failed::hash.eq(&source_tx.hash),
+            failed::height.eq(source_tx.height as i64),
+            failed::tx_index.eq(source_tx.index as i32),
+            failed::code.eq(source_tx.code as i32),
+            failed::codespace.eq(&source_tx.codespace),
+            failed::log.eq(&source_tx.log),
+        ))
+        .on_conflict_do_nothing()
+        .execute(db_connection)?;
+    Ok(())
+}
+
 pub fn get_transactions(
     config: &IndexerConfig,
     indexer_registry: &IndexerRegistry,
@@ -51,6 +74,24 @@ pub fn get_transactions(
     }
 }
 
+/// The stored transactions with heights in `range`, in height order.
+pub fn get_transactions_in_range(
+    db_connection: &PgConnection,
+    range: &HeightRange,
+) -> anyhow::Result<Vec<Response>> {
+    let txs = transaction
+        .filter(height.ge(range.start as i64))
+        .filter(height.le(range.end as i64))
+        .order(height.asc())
+        .load::<Transaction>(db_connection)?;
+    let mut responses = Vec::new();
+    for tx in txs {
+        let parsed_response: Response = serde_json::from_value(tx.response)?;
+        responses.push(parsed_response);
+    }
+    Ok(responses)
+}
+
 fn read_transaction(
     config: &IndexerConfig,
     db_connection: &PgConnection,
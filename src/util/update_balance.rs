@@ -1,4 +1,5 @@
 use super::gov_token::get_gov_token_address;
+use super::message_key::MessageKey;
 use crate::indexing::indexer_registry::IndexerRegistry;
 use bigdecimal::BigDecimal;
 use cosmwasm_std::Uint128;
@@ -30,6 +31,7 @@ pub fn update_balance<'a>(
     token_sender_address: &str,
     recipient: &str,
     balance_amount: u128,
+    message_key: &MessageKey,
 ) -> QueryResult<usize> {
     use crate::db::schema::cw20_transactions::dsl::*;
     let amount_converted: BigDecimal = BigDecimal::from(BigInt::from(balance_amount));
@@ -47,7 +49,13 @@ pub fn update_balance<'a>(
             recipient_address.eq(recipient),
             height.eq(&transaction_height),
             amount.eq(amount_converted),
+            tx_hash.eq(&message_key.tx_hash),
+            msg_index.eq(message_key.msg_index),
+            event_index.eq(message_key.event_index),
         ))
+        // Already recorded when this transaction was indexed before; skipping it
+        // also keeps the balance trigger from counting the transfer twice.
+        .on_conflict_do_nothing()
         .execute(db.into())
 }
 
@@ -85,6 +93,7 @@ pub fn update_balance_from_events(
                 sender,
                 receiver,
                 u128::from(parsed_amount),
+                &MessageKey::from_events(event_map, i),
             )
         } else {
             Ok(0)